@@ -1,40 +1,266 @@
-use bdk::bitcoin::Network;
+use crate::util::seed::{decrypt_seed, encrypt_seed};
+use crate::wallet::fees::{DEFAULT_FEE_CEILING, DEFAULT_FEE_FLOOR};
+use crate::wallet::util::{Descriptors, Multisig, ScriptType};
+use bdk::bitcoin::{Network, OutPoint};
+use bdk::blockchain::electrum::{ElectrumBlockchain, ElectrumBlockchainConfig};
+use bdk::blockchain::ConfigurableBlockchain;
 use bdk::wallet::Wallet;
 use bdk::{
     database::SqliteDatabase,
     keys::{ExtendedKey, GeneratableKey},
 };
+use boltz_client::network::electrum::ElectrumConfig;
+use boltz_client::network::Chain;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
 use clap::{error::Result, ArgMatches};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::str::FromStr;
 
-#[derive(Debug)]
 pub struct NetworkInfoModel {
     pub network: Network,
     pub electrum_url: String,
+    pub electrum_tls: bool,
+    pub electrum_validate_domain: bool,
+    pub electrum_timeout: u8,
+    /// sat/vB bounds for on-chain sends
+    pub fee_floor: f32,
+    pub fee_ceiling: f32,
     pub boltz_url: String,
+    /// public descriptors, readable without the passphrase
+    pub deposit_descriptor: String,
+    pub change_descriptor: String,
+    /// only set once unlocked
     mnemonic: Option<String>,
+    encrypted_mnemonic: Option<Vec<u8>>,
+    /// optional BIP39 passphrase, encrypted alongside the mnemonic unless prompted for
+    bip39_passphrase: Option<String>,
+    encrypted_bip39_passphrase: Option<Vec<u8>>,
+    /// never store the BIP39 passphrase, ask for it on every unlock
+    pub prompt_bip39_passphrase: bool,
+    /// script type the descriptors are derived from the seed with
+    pub script_type: ScriptType,
+    /// m-of-n policy with cosigner keys, None for single key wallets
+    pub multisig: Option<Multisig>,
+    /// created from an xpub or descriptor, there is no seed
+    pub watch_only: bool,
+}
+
+/// Shown for secrets in Debug output.
+const REDACTED: &str = "<redacted>";
+
+impl std::fmt::Debug for NetworkInfoModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkInfoModel")
+            .field("network", &self.network)
+            .field("electrum_url", &self.electrum_url)
+            .field("electrum_tls", &self.electrum_tls)
+            .field("electrum_validate_domain", &self.electrum_validate_domain)
+            .field("electrum_timeout", &self.electrum_timeout)
+            .field("fee_floor", &self.fee_floor)
+            .field("fee_ceiling", &self.fee_ceiling)
+            .field("boltz_url", &self.boltz_url)
+            .field("deposit_descriptor", &self.deposit_descriptor)
+            .field("change_descriptor", &self.change_descriptor)
+            .field("mnemonic", &self.mnemonic.as_ref().map(|_| REDACTED))
+            .field(
+                "bip39_passphrase",
+                &self.bip39_passphrase.as_ref().map(|_| REDACTED),
+            )
+            .field("encrypted", &self.encrypted_mnemonic.is_some())
+            .field("prompt_bip39_passphrase", &self.prompt_bip39_passphrase)
+            .field("script_type", &self.script_type)
+            .field("multisig", &self.multisig)
+            .field("watch_only", &self.watch_only)
+            .finish()
+    }
 }
 
 impl NetworkInfoModel {
     pub fn from_arg_matches(am: ArgMatches) -> Self {
         let electrum = am.get_one::<String>("electrum").unwrap();
         let boltz = am.get_one::<String>("boltz").unwrap();
+        let network = am.get_one::<Network>("network").unwrap();
+        // local regtest servers usually run without tls
+        let electrum_tls = am
+            .get_one::<bool>("electrum-tls")
+            .copied()
+            .unwrap_or(*network != Network::Regtest);
+        let electrum_validate_domain = am
+            .get_one::<bool>("electrum-validate-domain")
+            .copied()
+            .unwrap_or(electrum_tls);
+        let electrum_timeout = am.get_one::<u8>("electrum-timeout").unwrap();
+        let prompt_bip39_passphrase = am
+            .get_one::<String>("bip39-passphrase-policy")
+            .map(|policy| policy == "prompt")
+            .unwrap_or(false);
+        let script_type = am
+            .get_one::<ScriptType>("script-type")
+            .copied()
+            .unwrap_or_default();
         NetworkInfoModel {
-            network: Network::Testnet,
+            network: *network,
             electrum_url: electrum.to_string(),
+            electrum_tls,
+            electrum_validate_domain,
+            electrum_timeout: *electrum_timeout,
+            fee_floor: DEFAULT_FEE_FLOOR,
+            fee_ceiling: DEFAULT_FEE_CEILING,
             boltz_url: boltz.to_string(),
+            deposit_descriptor: String::new(),
+            change_descriptor: String::new(),
             mnemonic: None,
+            encrypted_mnemonic: None,
+            bip39_passphrase: None,
+            encrypted_bip39_passphrase: None,
+            prompt_bip39_passphrase,
+            script_type,
+            multisig: None,
+            watch_only: false,
         }
     }
-    pub fn update_mnemonic(&mut self, mnemonic: String) -> Result<&mut Self, String> //should return type be <Self>?
+    /// Makes this a watch-only wallet for the given public descriptors, with the script type they use.
+    pub fn update_watch_only(&mut self, descriptors: Descriptors) -> Result<&mut Self, String> {
+        if self.mnemonic.is_some() {
+            return Err("mnemonic exists.".to_string());
+        }
+        if let Some(script_type) = ScriptType::of_descriptor(&descriptors.deposit) {
+            self.script_type = script_type;
+        }
+        self.deposit_descriptor = descriptors.deposit;
+        self.change_descriptor = descriptors.change;
+        self.watch_only = true;
+        Ok(self)
+    }
+    /// Public descriptors of the seed for this wallet's script type or multisig policy.
+    fn derive_descriptors(
+        &self,
+        mnemonic: &str,
+        bip39_passphrase: &str,
+    ) -> Result<Descriptors, String> {
+        match &self.multisig {
+            Some(multisig) => {
+                Descriptors::new_public_multisig(mnemonic, bip39_passphrase, self.network, multisig)
+            }
+            None => {
+                Descriptors::new_public(mnemonic, bip39_passphrase, self.network, self.script_type)
+            }
+        }
+    }
+    /// Multisig wallets that cannot spend with this seed's signature alone.
+    pub fn needs_cosigners(&self) -> bool {
+        self.multisig
+            .as_ref()
+            .map_or(false, |multisig| multisig.threshold > 1)
+    }
+    pub fn update_mnemonic(
+        &mut self,
+        mnemonic: String,
+        bip39_passphrase: Option<String>,
+    ) -> Result<&mut Self, String> //should return type be <Self>?
     {
         if self.mnemonic.is_none() {
+            let descriptors =
+                self.derive_descriptors(&mnemonic, bip39_passphrase.as_deref().unwrap_or(""))?;
+            self.deposit_descriptor = descriptors.deposit;
+            self.change_descriptor = descriptors.change;
             self.mnemonic = Some(mnemonic);
+            self.bip39_passphrase = bip39_passphrase;
             Ok(self) //should this return self.clone() ?
         } else {
             Err("mnemonic exists.".to_string())
         }
     }
+    /// The boltz chain for this network. Signet and regtest share testnet key derivation.
+    pub fn chain(&self) -> Chain {
+        match self.network {
+            Network::Bitcoin => Chain::Bitcoin,
+            _ => Chain::BitcoinTestnet,
+        }
+    }
+    /// Electrum settings for boltz swap scripts.
+    pub fn electrum_config(&self) -> ElectrumConfig {
+        ElectrumConfig::new(
+            self.chain(),
+            &self.electrum_url,
+            self.electrum_tls,
+            self.electrum_validate_domain,
+            self.electrum_timeout,
+        )
+    }
+    /// The same electrum server and settings as electrum_config, for the bdk wallet.
+    pub fn electrum_blockchain(&self) -> Result<ElectrumBlockchain, String> {
+        self.electrum_blockchain_with_stop_gap(20)
+    }
+    /// stop_gap is how many unused addresses in a row end the address scan.
+    pub fn electrum_blockchain_with_stop_gap(
+        &self,
+        stop_gap: usize,
+    ) -> Result<ElectrumBlockchain, String> {
+        let scheme = if self.electrum_tls { "ssl" } else { "tcp" };
+        let config = ElectrumBlockchainConfig {
+            url: format!("{}://{}", scheme, self.electrum_url),
+            socks5: None,
+            retry: 3,
+            timeout: Some(self.electrum_timeout),
+            stop_gap,
+            validate_domain: self.electrum_validate_domain,
+        };
+        ElectrumBlockchain::from_config(&config).map_err(|e| e.to_string())
+    }
+    pub fn public_descriptors(&self) -> Descriptors {
+        Descriptors {
+            deposit: self.deposit_descriptor.clone(),
+            change: self.change_descriptor.clone(),
+        }
+    }
+    pub fn is_locked(&self) -> bool {
+        self.mnemonic.is_none()
+    }
+    /// Wallets created before seed encryption keep a plaintext mnemonic until it is encrypted.
+    pub fn has_plaintext_seed(&self) -> bool {
+        self.mnemonic.is_some() && self.encrypted_mnemonic.is_none()
+    }
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        if !self.is_locked() {
+            return Ok(());
+        }
+        match &self.encrypted_mnemonic {
+            Some(encrypted) => {
+                let mnemonic = decrypt_seed(encrypted, passphrase)?;
+                if let Some(encrypted) = &self.encrypted_bip39_passphrase {
+                    self.bip39_passphrase = Some(decrypt_seed(encrypted, passphrase)?);
+                }
+                self.mnemonic = Some(mnemonic);
+                Ok(())
+            }
+            None => Err("No seed found in db.".to_string()),
+        }
+    }
+    /// True when unlocked but the BIP39 passphrase still has to be asked for.
+    pub fn needs_bip39_passphrase(&self) -> bool {
+        self.prompt_bip39_passphrase && self.bip39_passphrase.is_none()
+    }
+    /// Sets a prompted BIP39 passphrase and derives the wallet it opens. Every passphrase opens
+    /// a valid wallet and none is stored, so a decoy passphrase cannot be told from the real one.
+    pub fn set_bip39_passphrase(&mut self, bip39_passphrase: String) -> Result<(), String> {
+        let mnemonic = match &self.mnemonic {
+            Some(mnemonic) => mnemonic,
+            None => return Err("Wallet is locked.".to_string()),
+        };
+        let descriptors = self.derive_descriptors(mnemonic, &bip39_passphrase)?;
+        self.deposit_descriptor = descriptors.deposit;
+        self.change_descriptor = descriptors.change;
+        self.bip39_passphrase = Some(bip39_passphrase);
+        Ok(())
+    }
+    /// The BIP39 passphrase for seed derivation, empty if none was set.
+    pub fn bip39_passphrase(&self) -> String {
+        self.bip39_passphrase.clone().unwrap_or_default()
+    }
     pub fn display_secret(&self) -> String {
         if self.mnemonic.is_none() {
             "None".to_string()
@@ -44,6 +270,14 @@ impl NetworkInfoModel {
     }
 }
 
+/// Accepts "mainnet" alongside the bitcoin crate names (bitcoin, testnet, signet, regtest).
+pub fn parse_network(network: &str) -> Result<Network, String> {
+    match network {
+        "mainnet" => Ok(Network::Bitcoin),
+        _ => Network::from_str(network).map_err(|e| e.to_string()),
+    }
+}
+
 // fn check_db_exists(path: &Path) -> Result<bool, sled::Error> {
 //     // Check if the directory already exists
 //     let already_exists = path.exists();
@@ -55,12 +289,16 @@ impl NetworkInfoModel {
 //     Ok(already_exists)
 // }
 
-pub fn create_db(wallet_info: NetworkInfoModel, path: &Path) -> Result<(), String> {
+pub fn create_db(
+    wallet_info: &NetworkInfoModel,
+    path: &Path,
+    passphrase: &str,
+) -> Result<(), String> {
     let already_exists = path.exists();
     if already_exists {
         return Err("Wallet already exists. Retry after swappy delete.".to_string());
     }
-    if wallet_info.mnemonic.is_none() {
+    if wallet_info.mnemonic.is_none() && !wallet_info.watch_only {
         return Err("No mnemonic to write to db".to_string());
     }
     // Open the sled database
@@ -69,31 +307,450 @@ pub fn create_db(wallet_info: NetworkInfoModel, path: &Path) -> Result<(), Strin
         .map_err(|e| e.to_string())?;
     db.insert(b"boltz", wallet_info.boltz_url.as_bytes())
         .unwrap();
-    // Insert wallet data (mnemonic and public descriptor)
-
-    db.insert(b"mnemonic", wallet_info.mnemonic.unwrap().as_bytes())
-        .unwrap();
+    db.insert(b"network", wallet_info.network.to_string().as_bytes())
+        .map_err(|e| e.to_string())?;
+    db.insert(
+        b"electrum_tls",
+        wallet_info.electrum_tls.to_string().as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    db.insert(
+        b"electrum_validate_domain",
+        wallet_info.electrum_validate_domain.to_string().as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    db.insert(
+        b"electrum_timeout",
+        wallet_info.electrum_timeout.to_string().as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    db.insert(
+        b"script_type",
+        wallet_info.script_type.to_string().as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    // Insert wallet data (encrypted mnemonic and public descriptors)
+    if wallet_info.watch_only {
+        db.insert(b"watch_only", "true".as_bytes())
+            .map_err(|e| e.to_string())?;
+        write_descriptors(&db, wallet_info)?;
+    } else {
+        write_seed(&db, wallet_info, passphrase)?;
+    }
     // You may also want to store other wallet-related information
 
     drop(db);
-    println!("Written to db.");
+    eprintln!("Written to db.");
     Ok(())
 }
 
+fn write_seed(
+    db: &sled::Db,
+    wallet_info: &NetworkInfoModel,
+    passphrase: &str,
+) -> Result<(), String> {
+    let mnemonic = match &wallet_info.mnemonic {
+        Some(mnemonic) => mnemonic,
+        None => return Err("Wallet is locked.".to_string()),
+    };
+    db.insert(b"encrypted_mnemonic", encrypt_seed(mnemonic, passphrase)?)
+        .map_err(|e| e.to_string())?;
+    if wallet_info.prompt_bip39_passphrase {
+        db.insert(b"prompt_bip39_passphrase", "true".as_bytes())
+            .map_err(|e| e.to_string())?;
+    } else if let Some(bip39_passphrase) = &wallet_info.bip39_passphrase {
+        db.insert(
+            b"encrypted_bip39_passphrase",
+            encrypt_seed(bip39_passphrase, passphrase)?,
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(multisig) = &wallet_info.multisig {
+        db.insert(
+            b"multisig_threshold",
+            multisig.threshold.to_string().as_bytes(),
+        )
+        .map_err(|e| e.to_string())?;
+        db.insert(
+            b"multisig_cosigners",
+            multisig.cosigners.join(",").as_bytes(),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    // prompted passphrase wallets derive their descriptors on unlock, storing them would reveal which passphrase is real
+    if wallet_info.prompt_bip39_passphrase {
+        db.remove(b"deposit_descriptor")
+            .map_err(|e| e.to_string())?;
+        db.remove(b"change_descriptor").map_err(|e| e.to_string())?;
+    } else {
+        write_descriptors(db, wallet_info)?;
+    }
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn write_descriptors(db: &sled::Db, wallet_info: &NetworkInfoModel) -> Result<(), String> {
+    db.insert(
+        b"deposit_descriptor",
+        wallet_info.deposit_descriptor.as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    db.insert(
+        b"change_descriptor",
+        wallet_info.change_descriptor.as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replaces the plaintext mnemonic of an older wallet with an encrypted one.
+///
+/// sled only appends, so removing the key would leave the mnemonic in its log files. Instead
+/// everything but the mnemonic is copied into a fresh db which then replaces the old one. The
+/// deleted files are not overwritten, so the filesystem may still hold the plaintext.
+pub fn encrypt_plaintext_seed(
+    path: &Path,
+    wallet_info: &NetworkInfoModel,
+    passphrase: &str,
+) -> Result<(), String> {
+    let fresh_path = path.with_extension("migrating");
+    if fresh_path.exists() {
+        std::fs::remove_dir_all(&fresh_path).map_err(|e| e.to_string())?;
+    }
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let fresh = sled::open(&fresh_path).map_err(|e| e.to_string())?;
+    for entry in db.iter() {
+        let (key, value) = entry.map_err(|e| e.to_string())?;
+        if &*key != b"mnemonic" {
+            fresh.insert(key, value).map_err(|e| e.to_string())?;
+        }
+    }
+    // swaps, utxos
+    for name in db.tree_names() {
+        if name == db.name() {
+            continue;
+        }
+        let tree = db.open_tree(&name).map_err(|e| e.to_string())?;
+        let fresh_tree = fresh.open_tree(&name).map_err(|e| e.to_string())?;
+        for entry in tree.iter() {
+            let (key, value) = entry.map_err(|e| e.to_string())?;
+            fresh_tree.insert(key, value).map_err(|e| e.to_string())?;
+        }
+    }
+    write_seed(&fresh, wallet_info, passphrase)?;
+    drop(fresh);
+    drop(db);
+    std::fs::remove_dir_all(path).map_err(|e| e.to_string())?;
+    std::fs::rename(&fresh_path, path).map_err(|e| e.to_string())
+}
+
+/// Overwrites a single setting of an existing wallet.
+pub fn set_config(path: &Path, key: &str, value: &str) -> Result<(), String> {
+    if !path.exists() {
+        return Err("No wallet found. Create one with swappy create.".to_string());
+    }
+    match key {
+        "electrum_tls" | "electrum_validate_domain" => {
+            value.parse::<bool>().map_err(|e| e.to_string())?;
+        }
+        "electrum_timeout" => {
+            value.parse::<u8>().map_err(|e| e.to_string())?;
+        }
+        "fee_floor" | "fee_ceiling" => {
+            let rate = value.parse::<f32>().map_err(|e| e.to_string())?;
+            if !(rate > 0.0 && rate.is_finite()) {
+                return Err(format!("{} must be a positive sat/vB rate.", key));
+            }
+        }
+        _ => {}
+    }
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    db.insert(key.as_bytes(), value.as_bytes())
+        .map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Reads an optional setting, falling back to default for wallets created before it existed.
+fn read_setting<T: FromStr>(db: &sled::Db, key: &str, default: T) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    match db.get(key).map_err(|e| e.to_string())? {
+        Some(value) => std::str::from_utf8(&value)
+            .map_err(|e| e.to_string())?
+            .parse::<T>()
+            .map_err(|e| format!("Invalid {}: {}", key, e)),
+        None => Ok(default),
+    }
+}
+
 pub fn read_db(path: &Path) -> Result<NetworkInfoModel, String> {
+    if !path.exists() {
+        return Err("No wallet found. Create one with swappy create.".to_string());
+    }
     let db = sled::open(path).unwrap();
     let value = db.get("electrum").unwrap().unwrap();
     let electrum = std::str::from_utf8(&value).unwrap();
     let value = db.get("boltz").unwrap().unwrap();
     let boltz = std::str::from_utf8(&value).unwrap();
-    let value = db.get("mnemonic").unwrap().unwrap();
-    let mnemonic = std::str::from_utf8(&value).unwrap();
-    Ok(NetworkInfoModel {
-        network: Network::Testnet,
+    // wallets created before network selection are testnet
+    let network = match db.get("network").map_err(|e| e.to_string())? {
+        Some(value) => parse_network(std::str::from_utf8(&value).unwrap())?,
+        None => Network::Testnet,
+    };
+    let electrum_tls = read_setting(&db, "electrum_tls", network != Network::Regtest)?;
+    let electrum_validate_domain = read_setting(&db, "electrum_validate_domain", electrum_tls)?;
+    let electrum_timeout = read_setting(&db, "electrum_timeout", 10)?;
+    let mut wallet_info = NetworkInfoModel {
+        network,
         electrum_url: electrum.to_string(),
+        electrum_tls,
+        electrum_validate_domain,
+        electrum_timeout,
+        fee_floor: read_setting(&db, "fee_floor", DEFAULT_FEE_FLOOR)?,
+        fee_ceiling: read_setting(&db, "fee_ceiling", DEFAULT_FEE_CEILING)?,
         boltz_url: boltz.to_string(),
-        mnemonic: Some(mnemonic.to_string()),
-    })
+        deposit_descriptor: String::new(),
+        change_descriptor: String::new(),
+        mnemonic: None,
+        encrypted_mnemonic: None,
+        bip39_passphrase: None,
+        encrypted_bip39_passphrase: None,
+        prompt_bip39_passphrase: read_setting(&db, "prompt_bip39_passphrase", false)?,
+        script_type: read_setting(&db, "script_type", ScriptType::default())?,
+        multisig: match read_setting(&db, "multisig_threshold", 0)? {
+            0 => None,
+            threshold => Some(Multisig {
+                threshold,
+                cosigners: read_setting(&db, "multisig_cosigners", String::new())?
+                    .split(',')
+                    .map(str::to_string)
+                    .collect(),
+            }),
+        },
+        watch_only: read_setting(&db, "watch_only", false)?,
+    };
+    match db.get("encrypted_mnemonic").map_err(|e| e.to_string())? {
+        Some(encrypted) => {
+            wallet_info.encrypted_mnemonic = Some(encrypted.to_vec());
+            wallet_info.encrypted_bip39_passphrase = db
+                .get("encrypted_bip39_passphrase")
+                .map_err(|e| e.to_string())?
+                .map(|encrypted| encrypted.to_vec());
+            if !wallet_info.prompt_bip39_passphrase {
+                wallet_info.deposit_descriptor =
+                    read_setting(&db, "deposit_descriptor", String::new())?;
+                wallet_info.change_descriptor =
+                    read_setting(&db, "change_descriptor", String::new())?;
+            }
+        }
+        None if wallet_info.watch_only => {
+            wallet_info.deposit_descriptor =
+                read_setting(&db, "deposit_descriptor", String::new())?;
+            wallet_info.change_descriptor = read_setting(&db, "change_descriptor", String::new())?;
+        }
+        None => {
+            let value = db.get("mnemonic").unwrap().unwrap();
+            let mnemonic = std::str::from_utf8(&value).unwrap();
+            wallet_info.update_mnemonic(mnemonic.to_string(), None)?;
+        }
+    }
+    Ok(wallet_info)
+}
+
+// snake_case like the json output tags; the aliases read swaps saved before the rename
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapKind {
+    #[serde(alias = "Submarine")]
+    Submarine,
+    #[serde(alias = "Reverse")]
+    Reverse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapState {
+    /// Swap created with boltz; submarine: waiting for our lockup, reverse: waiting for invoice payment.
+    #[serde(alias = "Created")]
+    Created,
+    /// Submarine: our lockup tx is out, waiting for boltz to pay the invoice.
+    #[serde(alias = "Funded")]
+    Funded,
+    /// Reverse: boltz locked up funds, we can claim them.
+    #[serde(alias = "Claimable")]
+    Claimable,
+    /// Reverse: our claim tx has been broadcast.
+    #[serde(alias = "Claimed")]
+    Claimed,
+    /// Submarine: boltz failed to pay, funds must be refunded after the timeout.
+    #[serde(alias = "Refundable")]
+    Refundable,
+    /// Submarine: our refund tx has been broadcast.
+    #[serde(alias = "Refunded")]
+    Refunded,
+    /// Swap completed on both sides.
+    #[serde(alias = "Settled")]
+    Settled,
+    /// Swap failed before any of our funds were locked.
+    #[serde(alias = "Failed")]
+    Failed,
+}
+
+impl SwapState {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            SwapState::Claimed | SwapState::Refunded | SwapState::Settled | SwapState::Failed
+        )
+    }
+}
+
+/// Everything needed to finish a swap after a restart. Keys are re-derived from the mnemonic using key_index.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SwapModel {
+    pub id: String,
+    pub kind: SwapKind,
+    pub state: SwapState,
+    pub key_index: u64,
+    pub redeem_script: String,
+    /// hex encoded; only reverse swaps hold a preimage
+    pub preimage: Option<String>,
+    pub invoice: String,
+    pub lockup_address: String,
+    /// sats locked on chain: what we lock up (submarine) or what boltz locks for us to claim (reverse)
+    pub amount: u64,
+    pub timeout_block_height: u64,
+    /// where claimed (reverse) or refunded (submarine) funds are sent
+    pub output_address: String,
+    /// last status reported by boltz
+    pub boltz_status: Option<String>,
+    /// our lockup tx (submarine)
+    pub lockup_txid: Option<String>,
+    /// our claim or refund tx
+    pub spend_txid: Option<String>,
+}
+
+impl std::fmt::Debug for SwapModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SwapModel")
+            .field("id", &self.id)
+            .field("kind", &self.kind)
+            .field("state", &self.state)
+            .field("key_index", &self.key_index)
+            .field("redeem_script", &self.redeem_script)
+            .field("preimage", &self.preimage.as_ref().map(|_| REDACTED))
+            .field("invoice", &self.invoice)
+            .field("lockup_address", &self.lockup_address)
+            .field("amount", &self.amount)
+            .field("timeout_block_height", &self.timeout_block_height)
+            .field("output_address", &self.output_address)
+            .field("boltz_status", &self.boltz_status)
+            .field("lockup_txid", &self.lockup_txid)
+            .field("spend_txid", &self.spend_txid)
+            .finish()
+    }
+}
+
+pub fn next_swap_key_index(path: &Path) -> Result<u64, String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let next = db
+        .update_and_fetch(b"swap_key_index", |old| {
+            let index = match old {
+                Some(bytes) => u64::from_be_bytes(bytes.try_into().unwrap()) + 1,
+                None => 0,
+            };
+            Some(index.to_be_bytes().to_vec())
+        })
+        .map_err(|e| e.to_string())?
+        .unwrap();
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(u64::from_be_bytes(next[..].try_into().unwrap()))
+}
+
+/// A restored wallet has lost the record of which swap keys it used. Continuing from a random
+/// offset far above the indexes a wallet reaches by counting from 0 avoids reusing them.
+pub fn randomize_swap_key_index(path: &Path) -> Result<(), String> {
+    let offset = (1u64 << 24) + u64::from(OsRng.next_u32() >> 2);
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    db.insert(b"swap_key_index", offset.to_be_bytes().to_vec())
+        .map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn save_swap(path: &Path, swap: &SwapModel) -> Result<(), String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let swaps = db.open_tree(b"swaps").map_err(|e| e.to_string())?;
+    let value = serde_json::to_vec(swap).map_err(|e| e.to_string())?;
+    swaps
+        .insert(swap.id.as_bytes(), value)
+        .map_err(|e| e.to_string())?;
+    // a swap record may be the only way back to locked funds; make sure it hits disk
+    swaps.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn read_swap(path: &Path, id: &str) -> Result<SwapModel, String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let swaps = db.open_tree(b"swaps").map_err(|e| e.to_string())?;
+    match swaps.get(id.as_bytes()).map_err(|e| e.to_string())? {
+        Some(value) => serde_json::from_slice(&value).map_err(|e| e.to_string()),
+        None => Err(format!("No swap with id {}", id)),
+    }
+}
+
+pub fn read_swaps(path: &Path) -> Result<Vec<SwapModel>, String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let swaps = db.open_tree(b"swaps").map_err(|e| e.to_string())?;
+    let mut result = vec![];
+    for entry in swaps.iter() {
+        let (_, value) = entry.map_err(|e| e.to_string())?;
+        result.push(serde_json::from_slice(&value).map_err(|e| e.to_string())?);
+    }
+    Ok(result)
+}
+
+/// Coin control state of one of our outputs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UtxoModel {
+    pub frozen: bool,
+    pub label: Option<String>,
+}
+
+pub fn save_utxo(path: &Path, outpoint: &OutPoint, utxo: &UtxoModel) -> Result<(), String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let utxos = db.open_tree(b"utxos").map_err(|e| e.to_string())?;
+    if *utxo == UtxoModel::default() {
+        utxos
+            .remove(outpoint.to_string().as_bytes())
+            .map_err(|e| e.to_string())?;
+    } else {
+        let value = serde_json::to_vec(utxo).map_err(|e| e.to_string())?;
+        utxos
+            .insert(outpoint.to_string().as_bytes(), value)
+            .map_err(|e| e.to_string())?;
+    }
+    utxos.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Frozen flags and labels by outpoint; outputs without either are absent.
+pub fn read_utxos(path: &Path) -> Result<HashMap<OutPoint, UtxoModel>, String> {
+    let db = sled::open(path).map_err(|e| e.to_string())?;
+    let utxos = db.open_tree(b"utxos").map_err(|e| e.to_string())?;
+    let mut result = HashMap::new();
+    for entry in utxos.iter() {
+        let (key, value) = entry.map_err(|e| e.to_string())?;
+        let outpoint =
+            OutPoint::from_str(&String::from_utf8_lossy(&key)).map_err(|e| e.to_string())?;
+        result.insert(
+            outpoint,
+            serde_json::from_slice(&value).map_err(|e| e.to_string())?,
+        );
+    }
+    Ok(result)
 }
 
 pub struct WalletInfoModel {
@@ -113,3 +770,198 @@ fn create_wallet_db(wallet_info: WalletInfoModel) -> () {
     // )
     // .unwrap();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let wallet_info = NetworkInfoModel {
+            network: Network::Regtest,
+            electrum_url: "127.0.0.1:50001".to_string(),
+            electrum_tls: false,
+            electrum_validate_domain: false,
+            electrum_timeout: 10,
+            fee_floor: DEFAULT_FEE_FLOOR,
+            fee_ceiling: DEFAULT_FEE_CEILING,
+            boltz_url: "http://127.0.0.1:9001".to_string(),
+            deposit_descriptor: String::new(),
+            change_descriptor: String::new(),
+            mnemonic: Some("rebel opinion faculty ticket".to_string()),
+            encrypted_mnemonic: None,
+            bip39_passphrase: Some("hunter2".to_string()),
+            encrypted_bip39_passphrase: None,
+            prompt_bip39_passphrase: false,
+            script_type: ScriptType::Wpkh,
+            multisig: None,
+            watch_only: false,
+        };
+        let debug = format!("{:#?}", wallet_info);
+        assert!(!debug.contains("rebel"));
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains(REDACTED));
+
+        let swap = SwapModel {
+            id: "swap".to_string(),
+            kind: SwapKind::Reverse,
+            state: SwapState::Created,
+            key_index: 0,
+            redeem_script: String::new(),
+            preimage: Some("deadbeef".to_string()),
+            invoice: String::new(),
+            lockup_address: String::new(),
+            amount: 50_000,
+            timeout_block_height: 100,
+            output_address: String::new(),
+            boltz_status: None,
+            lockup_txid: None,
+            spend_txid: None,
+        };
+        assert!(!format!("{:?}", swap).contains("deadbeef"));
+    }
+
+    #[test]
+    fn test_prompted_bip39_passphrase_opens_its_wallet() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let descriptors =
+            Descriptors::new_public(mnemonic, "hunter2", Network::Testnet, ScriptType::Wpkh)
+                .unwrap();
+        let mut wallet_info = NetworkInfoModel {
+            network: Network::Testnet,
+            electrum_url: String::new(),
+            electrum_tls: true,
+            electrum_validate_domain: true,
+            electrum_timeout: 10,
+            fee_floor: DEFAULT_FEE_FLOOR,
+            fee_ceiling: DEFAULT_FEE_CEILING,
+            boltz_url: String::new(),
+            deposit_descriptor: String::new(),
+            change_descriptor: String::new(),
+            mnemonic: Some(mnemonic.to_string()),
+            encrypted_mnemonic: None,
+            bip39_passphrase: None,
+            encrypted_bip39_passphrase: None,
+            prompt_bip39_passphrase: true,
+            script_type: ScriptType::Wpkh,
+            multisig: None,
+            watch_only: false,
+        };
+        assert!(wallet_info.needs_bip39_passphrase());
+        wallet_info
+            .set_bip39_passphrase("hunter3".to_string())
+            .unwrap();
+        assert_ne!(wallet_info.deposit_descriptor, descriptors.deposit);
+        wallet_info
+            .set_bip39_passphrase("hunter2".to_string())
+            .unwrap();
+        assert!(!wallet_info.needs_bip39_passphrase());
+        assert_eq!(wallet_info.bip39_passphrase(), "hunter2");
+        assert_eq!(wallet_info.deposit_descriptor, descriptors.deposit);
+    }
+
+    #[test]
+    fn test_encrypt_plaintext_seed() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let path = std::env::temp_dir().join(format!("swappy-test-migrate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = sled::open(&path).unwrap();
+        db.insert(b"electrum", "127.0.0.1:50001".as_bytes())
+            .unwrap();
+        db.insert(b"boltz", "127.0.0.1:9001".as_bytes()).unwrap();
+        db.insert(b"network", "regtest".as_bytes()).unwrap();
+        db.insert(b"mnemonic", mnemonic.as_bytes()).unwrap();
+        db.open_tree(b"swaps")
+            .unwrap()
+            .insert(b"swap-id", "{}".as_bytes())
+            .unwrap();
+        drop(db);
+
+        let wallet_info = read_db(&path).unwrap();
+        assert!(wallet_info.has_plaintext_seed());
+        encrypt_plaintext_seed(&path, &wallet_info, "hunter2").unwrap();
+
+        let db = sled::open(&path).unwrap();
+        assert!(db.get(b"mnemonic").unwrap().is_none());
+        assert!(db
+            .open_tree(b"swaps")
+            .unwrap()
+            .get(b"swap-id")
+            .unwrap()
+            .is_some());
+        drop(db);
+        let mut wallet_info = read_db(&path).unwrap();
+        wallet_info.unlock("hunter2").unwrap();
+        assert_eq!(wallet_info.display_secret(), mnemonic);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watch_only_script_type_roundtrip() {
+        let tpub = "[7b51f3f7/86'/1'/0']tpubDCCnk1bwtxqNaFbQstA7iGuzKkooWrZZ6HxHeEQ3dZbKCDftjW7pLGMjdwh1mKXK52SW6TYyoGjzFWaaSAVLCs7aq2Y4TZyaWgocm9GxuoQ";
+        let path =
+            std::env::temp_dir().join(format!("swappy-test-watch-only-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let mut wallet_info = NetworkInfoModel {
+            network: Network::Regtest,
+            electrum_url: "127.0.0.1:50001".to_string(),
+            electrum_tls: false,
+            electrum_validate_domain: false,
+            electrum_timeout: 10,
+            fee_floor: DEFAULT_FEE_FLOOR,
+            fee_ceiling: DEFAULT_FEE_CEILING,
+            boltz_url: "http://127.0.0.1:9001".to_string(),
+            deposit_descriptor: String::new(),
+            change_descriptor: String::new(),
+            mnemonic: None,
+            encrypted_mnemonic: None,
+            bip39_passphrase: None,
+            encrypted_bip39_passphrase: None,
+            prompt_bip39_passphrase: false,
+            script_type: ScriptType::Wpkh,
+            multisig: None,
+            watch_only: false,
+        };
+        let descriptors =
+            Descriptors::from_xpub(tpub, Network::Regtest, Some(ScriptType::Tr)).unwrap();
+        wallet_info.update_watch_only(descriptors).unwrap();
+        create_db(&wallet_info, &path, "").unwrap();
+
+        let read = read_db(&path).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+        assert!(read.watch_only);
+        assert_eq!(read.script_type, ScriptType::Tr);
+        assert_eq!(read.deposit_descriptor, wallet_info.deposit_descriptor);
+    }
+
+    #[test]
+    fn test_swap_state_names() {
+        assert_eq!(
+            serde_json::to_string(&SwapState::Refundable).unwrap(),
+            r#""refundable""#
+        );
+        // swaps saved before the snake_case rename
+        let state: SwapState = serde_json::from_str(r#""Refundable""#).unwrap();
+        assert_eq!(state, SwapState::Refundable);
+    }
+
+    #[test]
+    fn test_utxo_roundtrip() {
+        let path = std::env::temp_dir().join(format!("swappy-test-utxos-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let outpoint = OutPoint::from_str(
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:1",
+        )
+        .unwrap();
+        let utxo = UtxoModel {
+            frozen: true,
+            label: Some("kyc".to_string()),
+        };
+        save_utxo(&path, &outpoint, &utxo).unwrap();
+        assert_eq!(read_utxos(&path).unwrap().get(&outpoint), Some(&utxo));
+        // an unfrozen, unlabelled coin is not kept
+        save_utxo(&path, &outpoint, &UtxoModel::default()).unwrap();
+        assert!(read_utxos(&path).unwrap().is_empty());
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -1,55 +1,90 @@
 mod db;
+mod swap;
 mod util;
 mod wallet;
-use bdk::{FeeRate, SignOptions};
-use boltz_client::network::electrum::ElectrumConfig;
-use boltz_client::network::Chain;
-use boltz_client::swaps::bitcoin::{BtcSwapScript, BtcSwapTx};
-use boltz_client::util::derivation::SwapKey;
-use boltz_client::util::preimage::Preimage;
-use boltz_client::KeyPair;
+use bdk::bitcoin::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::{Address, Amount, OutPoint, Transaction, Txid};
+use bdk::blockchain::{Blockchain, ElectrumBlockchain};
+use bdk::wallet::AddressIndex::{self, LastUnused};
+use bdk::{database::SqliteDatabase, LocalUtxo, SyncOptions, Wallet};
+use bdk::{FeeRate, SignOptions, TransactionDetails};
 use clap::{Arg, Command};
-use db::{create_db, read_db, NetworkInfoModel};
+use db::{
+    create_db, encrypt_plaintext_seed, parse_network, randomize_swap_key_index, read_db, read_swap,
+    read_swaps, read_utxos, save_swap, save_utxo, set_config, NetworkInfoModel, SwapKind,
+    SwapState, UtxoModel,
+};
 use lightning_invoice::Bolt11Invoice;
-use std::path::PathBuf;
-use wallet::util::{create_wallet, Descriptors};
-const SWAPPY_DIR: &str = ".swappy";
-use bdk::bitcoin::{Address, Amount, Transaction};
-use bdk::blockchain::{Blockchain, ElectrumBlockchain};
-use bdk::{
-    database::SqliteDatabase, electrum_client::Client, wallet::AddressIndex::LastUnused,
-    SyncOptions, Wallet,
+use std::path::{Path, PathBuf};
+use swap::util::{
+    advance_swap, check_refundable, create_reverse_submarine_swap, create_submarine_swap,
+    get_pair_info, get_tip_height, refund_swap, resume_swaps, SwapQuote,
+};
+use util::output::{
+    emit, error, info, is_json, message, set_json, ConfigInfo, Output, PsbtInfo, SwapInfo,
+    TransactionInfo, UtxoInfo,
 };
-use boltz_client::swaps::boltz::{
-    BoltzApiClient, CreateSwapRequest, SwapStatusRequest, SwapType, BOLTZ_TESTNET_URL,
+use util::wallets;
+use wallet::fees::{
+    check_fee_rate, cpfp_child_fee, resolve_fee_rate, transaction_fee, FeeChoice, Priority,
+};
+use wallet::psbt::{combine_psbts, is_finalized, psbt_fee, read_psbt, signature_count, write_psbt};
+use wallet::util::{
+    create_wallet, multisig_xpub, validate_mnemonic, Descriptors, Multisig, ScriptType,
 };
 
 use std::str::FromStr;
 use std::thread;
 use std::time::{Duration, Instant};
 
+/// Unwraps a `Result<_, String>`, or reports the error and ends the command.
+macro_rules! or_exit {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(e) => {
+                error(&e);
+                return;
+            }
+        }
+    };
+}
+
 fn main() {
     std::env::set_var("RUST_BACKTRACE", "1");
     let api = Command::new("swappy")
         .color(clap::ColorChoice::Always)
         .about("\x1b[0;94mbitc✠in swap wallet\x1b[0m")
         .version("\x1b[0;1mv0.1.3\x1b[0m")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("print one json object per line instead of text")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("wallet")
+                .long("wallet")
+                .help("use this wallet instead of the current one")
+                .global(true),
+        )
+        .subcommand(create_command())
         .subcommand(
-            Command::new("create")
-                .about("create a wallet with network settings ")
-                .display_order(1)
-                .args([
-                    Arg::new("electrum")
-                        .short('e')
-                        .long("electrum")
-                        .help("electrum server url")
-                        .required(true),
-                    Arg::new("boltz")
-                        .short('b')
-                        .long("boltz")
-                        .help("boltz server url")
-                        .required(true),
-                ]),
+            Command::new("restore")
+                .about("restore a wallet from a bip39 mnemonic and rescan its history")
+                .display_order(2)
+                .args(network_args())
+                .args(seed_args())
+                .args(multisig_args())
+                .arg(
+                    Arg::new("gap-limit")
+                        .short('g')
+                        .long("gap-limit")
+                        .help("unused addresses in a row before the rescan stops")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20"),
+                ),
         )
         .subcommand(
             Command::new("delete")
@@ -70,124 +105,379 @@ fn main() {
         .subcommand(
             Command::new("receive")
                 .about("get a bitcoin address or ln invoice to get paid")
-                .display_order(5),
+                .display_order(5)
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("accept the swap quote without asking")
+                        .global(true)
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .subcommand(Command::new("chain").about("get a new bitcoin address"))
+                .subcommand(
+                    Command::new("ln")
+                        .about("get an ln invoice paid out onchain via boltz")
+                        .arg(
+                            Arg::new("amount")
+                                .help("amount to receive in sats")
+                                .value_parser(clap::value_parser!(u64)),
+                        ),
+                ),
         )
         .subcommand(
             Command::new("send")
                 .about("pay a bitcoin address or ln invoice")
-                .display_order(6),
+                .display_order(6)
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("accept the swap quote and fee without asking")
+                        .global(true)
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .args(fee_args().map(|arg| arg.global(true)))
+                .args(coin_args().map(|arg| arg.global(true)))
+                .subcommand(
+                    Command::new("chain").about("pay a bitcoin address").args([
+                        Arg::new("address").help("bitcoin address to pay"),
+                        Arg::new("amount")
+                            .help("amount to send in BTC")
+                            .value_parser(clap::value_parser!(f64)),
+                    ]),
+                )
+                .subcommand(
+                    Command::new("ln")
+                        .about("pay an ln invoice via boltz")
+                        .arg(Arg::new("invoice").help("bolt11 invoice to pay")),
+                ),
+        )
+        .subcommand(
+            Command::new("bump")
+                .about("replace an unconfirmed transaction with a higher fee (RBF)")
+                .display_order(6)
+                .args(accelerate_args("id of the transaction to replace")),
+        )
+        .subcommand(
+            Command::new("cpfp")
+                .about("spend our output of an unconfirmed transaction to raise its fee rate")
+                .display_order(6)
+                .args(accelerate_args("id of the transaction to accelerate")),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("change wallet settings")
+                .display_order(8)
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("set").about("set a wallet setting").args([
+                        Arg::new("key")
+                            .help("setting to change")
+                            .value_parser([
+                                "boltz",
+                                "electrum",
+                                "electrum_tls",
+                                "electrum_validate_domain",
+                                "electrum_timeout",
+                                "fee_floor",
+                                "fee_ceiling",
+                            ])
+                            .required(true),
+                        Arg::new("value").help("new value").required(true),
+                    ]),
+                ),
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("back up your wallet")
+                .display_order(9)
+                .subcommand_required(true)
+                .subcommand(Command::new("show-seed").about("print the mnemonic (careful!)"))
+                .subcommand(
+                    Command::new("show-xpub")
+                        .about("print this seed's multisig key to share with cosigners"),
+                ),
+        )
+        .subcommand(
+            Command::new("wallets")
+                .about("manage named wallets")
+                .display_order(11)
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("list wallets, * marks the current one"))
+                .subcommand(
+                    create_command()
+                        .about("create a named wallet")
+                        .arg(Arg::new("name").help("wallet name").required(true)),
+                )
+                .subcommand(
+                    Command::new("switch")
+                        .about("make a wallet the current one")
+                        .arg(Arg::new("name").help("wallet name").required(true)),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("delete a wallet (careful!)")
+                        .arg(Arg::new("name").help("wallet name").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("utxos")
+                .about("list, label and freeze coins")
+                .display_order(12)
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("list unspent coins"))
+                .subcommand(
+                    Command::new("freeze")
+                        .about("keep sends from spending these coins")
+                        .arg(outpoint_arg().num_args(1..)),
+                )
+                .subcommand(
+                    Command::new("unfreeze")
+                        .about("let sends spend these coins again")
+                        .arg(outpoint_arg().num_args(1..)),
+                )
+                .subcommand(
+                    Command::new("label")
+                        .about("label a coin, or clear its label")
+                        .args([outpoint_arg(), Arg::new("label").help("new label")]),
+                ),
+        )
+        .subcommand(
+            Command::new("psbt")
+                .about("build, sign and broadcast transactions in separate steps")
+                .display_order(10)
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("build an unsigned transaction")
+                        .args([
+                            Arg::new("address").help("bitcoin address").required(true),
+                            Arg::new("amount")
+                                .help("amount in BTC")
+                                .value_parser(clap::value_parser!(f64))
+                                .required_unless_present("max")
+                                .conflicts_with("max"),
+                            psbt_out_arg(),
+                        ])
+                        .args(fee_args())
+                        .args(coin_args()),
+                )
+                .subcommand(
+                    Command::new("sign")
+                        .about("sign with this wallet's seed")
+                        .args([psbt_file_arg(), psbt_out_arg()]),
+                )
+                .subcommand(
+                    Command::new("combine")
+                        .about("merge the signatures of several copies of a PSBT")
+                        .args([
+                            Arg::new("files")
+                                .help("base64 PSBT files")
+                                .num_args(2..)
+                                .required(true),
+                            psbt_out_arg(),
+                        ]),
+                )
+                .subcommand(
+                    Command::new("finalize")
+                        .about("finalize a fully signed PSBT")
+                        .args([psbt_file_arg(), psbt_out_arg()]),
+                )
+                .subcommand(
+                    Command::new("broadcast")
+                        .about("broadcast a finalized PSBT")
+                        .arg(psbt_file_arg()),
+                )
+                .subcommand(
+                    Command::new("decode")
+                        .about("show the inputs, outputs and signatures of a PSBT")
+                        .arg(psbt_file_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("swaps")
+                .about("manage lightning swaps")
+                .display_order(7)
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("list all swaps"))
+                .subcommand(Command::new("resume").about("claim or refund all unfinished swaps"))
+                .subcommand(
+                    Command::new("refund")
+                        .about("refund a submarine swap after its timeout")
+                        .args([
+                            Arg::new("id").help("swap id").required(true),
+                            Arg::new("address")
+                                .short('a')
+                                .long("address")
+                                .help("refund to this address instead of the wallet"),
+                            Arg::new("wait")
+                                .short('w')
+                                .long("wait")
+                                .help("wait for the timeout block height instead of failing")
+                                .action(clap::ArgAction::SetTrue),
+                        ]),
+                ),
         )
         .get_matches();
+    set_json(api.get_flag("json"));
+    if let Some(name) = api.get_one::<String>("wallet") {
+        // `wallets create <name>` selects the wallet it creates
+        let creating = matches!(
+            api.subcommand(),
+            Some(("wallets", wallets_matches)) if wallets_matches.subcommand_name() == Some("create")
+        );
+        if creating {
+            error("--wallet cannot be used with wallets create; the name selects the new wallet.");
+            return;
+        }
+        if let Err(e) = wallets::select(name) {
+            error(&e);
+            return;
+        }
+    }
 
     match api.subcommand() {
-        Some(("create", arg_matches)) => {
-            let path: PathBuf = match std::env::var("HOME") {
-                Ok(home_path) => {
-                    let mut full_path = PathBuf::from(home_path);
-                    full_path.push(SWAPPY_DIR);
-                    full_path
-                }
+        Some(("create", arg_matches)) => create(arg_matches),
+        Some(("restore", arg_matches)) => {
+            let path = match get_db_path() {
+                Ok(path) => path,
                 Err(e) => {
-                    eprintln!("Failed to get HOME path: {}", e);
+                    error(&e);
                     return;
                 }
             };
-            let already_exists = path.exists();
-            if already_exists {
-                eprintln!("Wallet already exists. Retry after swappy delete.");
+            if path.exists() {
+                error("Wallet already exists. Retry after swappy delete.");
                 return;
             }
 
+            let mnemonic =
+                match read_secret("SWAPPY_MNEMONIC", "Enter your 12 or 24 word mnemonic: ")
+                    .and_then(|mnemonic| validate_mnemonic(mnemonic.trim()))
+                {
+                    Ok(mnemonic) => mnemonic,
+                    Err(e) => {
+                        error(&format!("Invalid mnemonic: {}", e));
+                        return;
+                    }
+                };
+            let bip39_passphrase = match read_bip39_passphrase(arg_matches, false) {
+                Ok(bip39_passphrase) => bip39_passphrase,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
             let mut wallet_info = NetworkInfoModel::from_arg_matches(arg_matches.clone());
-            let mnemonic = wallet::util::create_mnemonic();
-            let _ = wallet_info.update_mnemonic(mnemonic.clone());
-            println!("Your mnemonic is: {}", mnemonic);
-            println!("Have you written down and secured your mnemonic? Type 'yes' to confirm:");
-            let mut confirmation = String::new();
-            std::io::stdin()
-                .read_line(&mut confirmation)
-                .expect("Failed to read line");
-            if confirmation.trim() != "yes" {
-                println!("Backup not confirmed. Exiting.");
+            wallet_info.multisig = match read_multisig(arg_matches, wallet_info.network) {
+                Ok(multisig) => multisig,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            if let Err(e) = wallet_info.update_mnemonic(mnemonic, bip39_passphrase) {
+                error(&e);
                 return;
             }
-            let descriptor = wallet::util::Descriptors::new_public(&mnemonic);
+            let passphrase = match new_passphrase() {
+                Ok(passphrase) => passphrase,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            if let Err(e) = create_db(&wallet_info, &path, &passphrase) {
+                error(&e);
+                return;
+            }
+            or_exit!(randomize_swap_key_index(&path));
 
-            let response = create_db(wallet_info, &path);
-            match response {
+            // history from another wallet with the same path would not match the descriptors
+            let sqlite_path = or_exit!(get_sqlite_path(&wallet_info));
+            if sqlite_path.exists() {
+                info(&format!(
+                    "Deleting the old wallet history in {}.",
+                    sqlite_path.display()
+                ));
+                or_exit!(std::fs::remove_file(&sqlite_path).map_err(|e| e.to_string()));
+            }
+            let gap_limit = *arg_matches.get_one::<usize>("gap-limit").unwrap();
+            info(&format!("Rescanning with a gap limit of {}...", gap_limit));
+            let wallet = or_exit!(init_public_wallet(&wallet_info));
+            let blockchain = or_exit!(wallet_info.electrum_blockchain_with_stop_gap(gap_limit));
+            match wallet.sync(&blockchain, SyncOptions::default()) {
                 Ok(()) => {
-                    println!("Successsfully created new wallet.");
+                    let balance = or_exit!(wallet.get_balance().map_err(|e| e.to_string()));
+                    message(&format!(
+                        "Restore Complete. Confirmed Balance: {}",
+                        balance.confirmed
+                    ));
                 }
                 Err(e) => {
-                    eprintln!("{}", e)
+                    error(&format!(
+                        "Wallet restored but the rescan failed: {}. Retry with swappy sync.",
+                        e
+                    ));
                 }
             }
         }
         Some(("read", _)) => {
-            let path: PathBuf = match std::env::var("HOME") {
-                Ok(home_path) => {
-                    let mut full_path = PathBuf::from(home_path);
-                    full_path.push(SWAPPY_DIR);
-                    full_path
-                }
-                Err(e) => {
-                    eprintln!("Failed to get HOME path: {}", e);
-                    return;
-                }
-            };
-            let wallet_info = read_db(&path).unwrap();
-            println!("{:#?}", wallet_info)
-        }
-
-        Some(("delete", _)) => {
-            let path: PathBuf = match std::env::var("HOME") {
-                Ok(home_path) => {
-                    let mut full_path = PathBuf::from(home_path);
-                    full_path.push(SWAPPY_DIR);
-                    full_path
-                }
+            let wallet_info = match get_db_path().and_then(|path| read_db(&path)) {
+                Ok(wallet_info) => wallet_info,
                 Err(e) => {
-                    eprintln!("Failed to get HOME path: {}", e);
+                    error(&e);
                     return;
                 }
             };
-            println!("DELETING WALLET! CAREFUL! ARE YOU SURE? Type 'yes' to confirm.");
-
-            let mut confirmation = String::new();
-            std::io::stdin()
-                .read_line(&mut confirmation)
-                .expect("Failed to read line");
-            if confirmation.trim() != "yes" {
-                println!("Aborting delete.");
-                return;
-            }
-            if let Err(e) = std::fs::remove_dir_all(path) {
-                eprintln!("Failed to delete wallet @ : {}", e);
+            if is_json() {
+                emit(Output::Config(ConfigInfo::from(&wallet_info)));
             } else {
-                println!("Wallet successfully deleted.");
+                println!("{:#?}", wallet_info)
             }
         }
+
+        Some(("delete", _)) => match wallets::current() {
+            Ok(name) => delete_wallet(&name),
+            Err(e) => error(&e),
+        },
         Some(("sync", _)) => {
-            let wallet_info = get_wallet_info().unwrap();
-            let wallet = init_public_wallet(&wallet_info).unwrap();
-            let electrum_url = format!("ssl://{}", wallet_info.electrum_url);
-            let client = Client::new(&electrum_url).unwrap();
-            let blockchain = ElectrumBlockchain::from(client);
+            let wallet_info = or_exit!(get_wallet_info());
+            let wallet = or_exit!(init_public_wallet(&wallet_info));
+            let blockchain = or_exit!(wallet_info.electrum_blockchain());
             match wallet.sync(&blockchain, SyncOptions::default()) {
                 Ok(()) => {
-                    println!("Sync Complete.");
+                    message("Sync Complete.");
                 }
                 Err(e) => {
-                    eprintln!("Sync Failed: {}", e);
+                    error(&format!("Sync Failed: {}", e));
                 }
             }
         }
         Some(("status", _)) => {
-            let wallet_info = get_wallet_info().unwrap();
-            let wallet = init_public_wallet(&wallet_info).unwrap();
-            let balance = wallet.get_balance().unwrap();
-            let history = wallet.list_transactions(true).unwrap();
+            let wallet_info = or_exit!(get_wallet_info());
+            let wallet = or_exit!(init_public_wallet(&wallet_info));
+            let balance = or_exit!(wallet.get_balance().map_err(|e| e.to_string()));
+            let history = or_exit!(wallet.list_transactions(true).map_err(|e| e.to_string()));
             let unconfirmed_balance = balance.untrusted_pending + balance.trusted_pending;
+            if is_json() {
+                emit(Output::Status {
+                    confirmed: balance.confirmed,
+                    unconfirmed: unconfirmed_balance,
+                    transactions: history
+                        .iter()
+                        .map(|tx| TransactionInfo {
+                            txid: tx.txid.to_string(),
+                            sent: tx.sent,
+                            received: tx.received,
+                            fee: tx.fee,
+                            confirmation_height: tx.confirmation_time.as_ref().map(|c| c.height),
+                        })
+                        .collect(),
+                });
+                return;
+            }
             println!("x------------------------x");
             println!("Unconfirmed Balance: {:#?}", unconfirmed_balance);
             println!("Confirmed Balance: {:#?}", balance.confirmed);
@@ -201,457 +491,1798 @@ fn main() {
             }
             println!("x------------------------x");
         }
-        Some(("receive", _)) => {
-            let wallet_info = get_wallet_info().unwrap();
-            let wallet = init_public_wallet(&wallet_info).unwrap();
-            println!("How to recieve?");
-            println!("0. Onchain");
-            println!("1. Lightning");
-            println!("Select 0/1 (default 0): ");
-            let mut confirmation = String::new();
-            std::io::stdin()
-                .read_line(&mut confirmation)
-                .expect("Failed to read line");
-            if confirmation.trim() == "1" {
-                println!("Getting invoice from boltz");
-                println!("Enter amount to receive in BTC: ");
-                let mut amount = String::new();
-                std::io::stdin()
-                    .read_line(&mut amount)
-                    .expect("Failed to read line");
-                let out_amount = amount.parse::<u64>().unwrap();
-                // construct SwapScript
-                match create_reverse_submarine_swap(&out_amount, &wallet_info) {
-                    Ok((invoice, id, rev_script, keypair, preimage)) => {
-                        println!("Complete payment of LN to :{}", invoice);
-                        // wait till someone pays the invoice
-                        let mut status = check_swap_status(&id, &rev_script).unwrap();
-                        let start = Instant::now();
-                        loop {
-                            if status {
-                                println!("Received payment from boltz");
-
-                                let network_config = ElectrumConfig::default_bitcoin();
-
-                                println!("Enter Return Address for payment");
-                                let mut return_address = String::new();
-                                std::io::stdin()
-                                    .read_line(&mut confirmation)
-                                    .expect("Failed to read line");
-                                // Create SwapTx
-                                let absolute_fees = 300;
-                                let mut rv_claim_tx = BtcSwapTx::new_claim(
-                                    rev_script,
-                                    return_address,
-                                    network_config.network(),
-                                )
-                                .unwrap();
-                                let _ = rv_claim_tx.fetch_utxo(out_amount, network_config.clone());
-                                //drain to wallet.get_address()
-                                let signed_tx =
-                                    rv_claim_tx.drain(keypair, preimage, absolute_fees).unwrap();
-                                let txid =
-                                    rv_claim_tx.broadcast(signed_tx, network_config).unwrap();
-                                println!("{}", txid);
-
-                                break;
-                            } else if start.elapsed() > Duration::from_secs(60) {
-                                println!("Timed out waiting for payment. Invoice is no longer valid. DO NOT PAY.");
-                                break;
-                            } else {
-                                eprintln!("No payment yet...");
-                                thread::sleep(Duration::from_secs(10));
-                            }
+        Some(("receive", receive_matches)) => {
+            let mut wallet_info = or_exit!(get_wallet_info());
+            let wallet = or_exit!(init_public_wallet(&wallet_info));
+            match receive_matches.subcommand() {
+                Some(("chain", _)) => receive_chain(&wallet),
+                Some(("ln", ln_matches)) => {
+                    let out_amount = match ln_matches.get_one::<u64>("amount") {
+                        Some(amount) => *amount,
+                        None => prompt("Enter amount to receive in sats: ")
+                            .parse::<u64>()
+                            .unwrap(),
+                    };
+                    if let Err(e) = unlock_wallet(&mut wallet_info) {
+                        error(&e);
+                        return;
+                    }
+                    receive_ln(
+                        &wallet,
+                        &wallet_info,
+                        out_amount,
+                        ln_matches.get_flag("yes"),
+                    );
+                }
+                _ => {
+                    info("How to recieve?");
+                    info("0. Onchain");
+                    info("1. Lightning");
+                    if prompt("Select 0/1 (default 0): ") == "1" {
+                        info("Getting invoice from boltz");
+                        let out_amount = prompt("Enter amount to receive in sats: ")
+                            .parse::<u64>()
+                            .unwrap();
+                        if let Err(e) = unlock_wallet(&mut wallet_info) {
+                            error(&e);
+                            return;
                         }
+                        receive_ln(
+                            &wallet,
+                            &wallet_info,
+                            out_amount,
+                            receive_matches.get_flag("yes"),
+                        );
+                    } else {
+                        receive_chain(&wallet);
                     }
-                    Err(e) => {
-                        eprintln!("Error creating reverse swap: {}", e);
+                }
+            }
+        }
+        Some(("send", send_matches)) => {
+            let mut wallet_info = or_exit!(get_wallet_info());
+            // watch-only wallets can only build unsigned transactions
+            let wallet = if wallet_info.watch_only {
+                or_exit!(init_public_wallet(&wallet_info))
+            } else {
+                if let Err(e) = unlock_wallet(&mut wallet_info) {
+                    error(&e);
+                    return;
+                }
+                or_exit!(init_secret_wallet(&wallet_info))
+            };
+            let fee_rate = match get_fee_rate(&wallet_info, send_matches) {
+                Ok(fee_rate) => fee_rate,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            let coins = match read_coin_control(send_matches) {
+                Ok(coins) => coins,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            match send_matches.subcommand() {
+                Some(("chain", chain_matches)) => {
+                    let address = match chain_matches.get_one::<String>("address") {
+                        Some(address) => address.clone(),
+                        None => prompt("Enter an address: "),
+                    };
+                    let address = match Address::from_str(&address) {
+                        Ok(address) => address,
+                        Err(e) => {
+                            error(&format!("Invalid address: {}", e));
+                            return;
+                        }
+                    };
+                    let btc_amount = match chain_matches.get_one::<f64>("amount") {
+                        Some(_) if coins.spend_all => {
+                            error("Pass either an amount or --max.");
+                            return;
+                        }
+                        Some(amount) => Some(*amount),
+                        None if coins.spend_all => None,
+                        None => Some(prompt("Enter amount in BTC: ").parse::<f64>().unwrap()),
+                    };
+                    send_chain(
+                        &wallet,
+                        &wallet_info,
+                        &address,
+                        btc_amount,
+                        fee_rate,
+                        &coins,
+                        chain_matches.get_flag("yes"),
+                    );
+                }
+                Some(("ln", ln_matches)) => {
+                    let invoice = match ln_matches.get_one::<String>("invoice") {
+                        Some(invoice) => invoice.clone(),
+                        None => prompt("Enter an invoice: "),
+                    };
+                    match Bolt11Invoice::from_str(&invoice) {
+                        Ok(invoice) => send_ln(
+                            &wallet,
+                            &wallet_info,
+                            &invoice,
+                            fee_rate,
+                            &coins,
+                            ln_matches.get_flag("yes"),
+                        ),
+                        Err(e) => {
+                            error(&format!("Invalid invoice: {}", e));
+                        }
                     }
-                };
+                }
+                _ => {
+                    // ask user to paste address or invoice
+                    let payment_info = prompt("Enter an address or invoice: ");
+                    if let Ok(address) = Address::from_str(&payment_info) {
+                        info("Resolved input to address. Paying...");
+                        let btc_amount = if coins.spend_all {
+                            None
+                        } else {
+                            Some(prompt("Enter amount in BTC: ").parse::<f64>().unwrap())
+                        };
+                        send_chain(
+                            &wallet,
+                            &wallet_info,
+                            &address,
+                            btc_amount,
+                            fee_rate,
+                            &coins,
+                            send_matches.get_flag("yes"),
+                        );
+                    } else if let Ok(invoice) = Bolt11Invoice::from_str(&payment_info) {
+                        info("Resolved input to invoice. Paying...");
+                        send_ln(
+                            &wallet,
+                            &wallet_info,
+                            &invoice,
+                            fee_rate,
+                            &coins,
+                            send_matches.get_flag("yes"),
+                        );
+                    } else {
+                        error("Could not resolve input to address or invoice");
+                    }
+                }
+            }
+        }
+        Some((command @ ("bump" | "cpfp"), accelerate_matches)) => {
+            let txid = match Txid::from_str(accelerate_matches.get_one::<String>("txid").unwrap()) {
+                Ok(txid) => txid,
+                Err(e) => {
+                    error(&format!("Invalid txid: {}", e));
+                    return;
+                }
+            };
+            let wallet_info = match get_unlocked_wallet_info() {
+                Ok(wallet_info) => wallet_info,
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            if wallet_info.needs_cosigners() {
+                error("Multisig wallets cannot sign this without their cosigners.");
+                return;
+            }
+            let fee_rate = match check_fee_rate(
+                *accelerate_matches.get_one::<f32>("fee-rate").unwrap(),
+                wallet_info.fee_floor,
+                wallet_info.fee_ceiling,
+            ) {
+                Ok(rate) => FeeRate::from_sat_per_vb(rate),
+                Err(e) => {
+                    error(&e);
+                    return;
+                }
+            };
+            let wallet = or_exit!(init_secret_wallet(&wallet_info));
+            let blockchain = or_exit!(wallet_info.electrum_blockchain());
+            let yes = accelerate_matches.get_flag("yes");
+            let frozen = or_exit!(get_db_path().and_then(|db_path| frozen_utxos(&db_path)));
+            let result = if command == "bump" {
+                bump_fee(&wallet, &txid, fee_rate, &frozen, &blockchain, yes)
             } else {
-                let address = wallet.get_address(LastUnused).unwrap();
-                println!("{:#?}", address.address.to_string());
-            }
-        }
-        Some(("send", _)) => {
-            let wallet_info = get_wallet_info().unwrap();
-            let wallet = init_secret_wallet(&wallet_info).unwrap();
-
-            // ask user to paste address or invoice
-            println!("Enter an address or invoice: ");
-            let mut payment_info = String::new();
-            std::io::stdin()
-                .read_line(&mut payment_info)
-                .expect("Failed to read input");
-
-            // check if address;
-            match Address::from_str(&payment_info) {
-                Ok(address) => {
-                    println!("Resolved input to address. Paying...");
-                    // make payment:
-                    println!("Enter amount in BTC: ");
-                    let mut amount = String::new();
-                    std::io::stdin()
-                        .read_line(&mut amount)
-                        .expect("Failed to read line");
-                    let btc_amount = amount.parse::<f64>().unwrap();
-                    let electrum_url = wallet_info.electrum_url;
-                    match (send_btc(&wallet, &address, btc_amount, electrum_url)) {
-                        Ok(transaction) => {
-                            println!("Payment successful: {:#?}", transaction);
+                cpfp(&wallet, &txid, fee_rate, &frozen, &blockchain, yes)
+            };
+            match result {
+                Ok((transaction, fee)) => {
+                    if command == "bump" {
+                        if let Err(e) = replace_lockup_txid(&txid, &transaction.txid()) {
+                            eprintln!("Error updating the swap funded by {}: {}", txid, e);
                         }
+                    }
+                    if is_json() {
+                        emit(Output::Transaction {
+                            txid: transaction.txid().to_string(),
+                            fee,
+                        });
+                    } else if command == "bump" {
+                        println!("Replaced {} with {}", txid, transaction.txid());
+                    } else {
+                        println!("Child {} pays for {}", transaction.txid(), txid);
+                    }
+                }
+                Err(e) => error(&format!("Error accelerating {}: {}", txid, e)),
+            }
+        }
+        Some(("config", config_matches)) => {
+            let db_path = or_exit!(get_db_path());
+            if let Some(("set", set_matches)) = config_matches.subcommand() {
+                let key = set_matches.get_one::<String>("key").unwrap();
+                let value = set_matches.get_one::<String>("value").unwrap();
+                match set_config(&db_path, key, value) {
+                    Ok(()) => {
+                        message(&format!("Set {} to {}.", key, value));
+                    }
+                    Err(e) => {
+                        error(&format!("Error updating config: {}", e));
+                    }
+                }
+            }
+        }
+        Some(("backup", backup_matches)) => {
+            if let Some(("show-seed", _)) = backup_matches.subcommand() {
+                let confirmation = prompt(
+                    "This prints your seed in the clear. Anyone who sees it can take your funds. Type 'yes' to continue:",
+                );
+                if confirmation != "yes" {
+                    error("Aborting backup.");
+                    return;
+                }
+                match get_unlocked_wallet_info() {
+                    Ok(wallet_info) => {
+                        reveal_seed(&wallet_info.display_secret());
+                        if !wallet_info.bip39_passphrase().is_empty() {
+                            info(
+                                "This wallet also uses a bip39 passphrase. Back it up separately.",
+                            );
+                        }
+                    }
+                    Err(e) => error(&e),
+                }
+            }
+            if let Some(("show-xpub", _)) = backup_matches.subcommand() {
+                match get_unlocked_wallet_info() {
+                    Ok(wallet_info) => show_multisig_xpub(&wallet_info),
+                    Err(e) => error(&e),
+                }
+            }
+        }
+        Some(("wallets", wallets_matches)) => match wallets_matches.subcommand() {
+            Some(("list", _)) => {
+                let (names, current) =
+                    match wallets::list().and_then(|names| Ok((names, wallets::current()?))) {
+                        Ok(list) => list,
                         Err(e) => {
-                            eprintln!("Error in payment: {}", e)
+                            error(&e);
+                            return;
                         }
                     };
+                if is_json() {
+                    emit(Output::Wallets {
+                        wallets: names,
+                        current,
+                    });
+                    return;
                 }
-                Err(e) => {
-                    println!("Could not resolve input to address. Checking invoice...");
-                    //check if invoice:
-                    match Bolt11Invoice::from_str(&payment_info) {
-                        Ok(invoice) => {
-                            println!("Resolved input to invoice. Paying...");
-                            //do submarine-swap
-
-                            match create_submarine_swap(&invoice.to_string(), &wallet_info) {
-                                Ok((funding_address, funding_amount)) => {
-                                    let funding_amount = funding_amount as f64;
-                                    //fund swap
-
-                                    match send_btc(
-                                        &wallet,
-                                        &Address::from_str(&funding_address).unwrap(),
-                                        funding_amount,
-                                        wallet_info.electrum_url,
-                                    ) {
-                                        Ok(transaction) => {
-                                            println!("Swap Funded: {:#?}", transaction);
-                                            println!("Invoice will be paid after 1 conf.")
-                                            //check if boltz paid LN addr?
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Error funding swap: {}", e);
-                                        }
-                                    };
-                                }
-                                Err(e) => {
-                                    eprintln!("Error creating submarine swap: {}", e)
-                                }
-                            };
+                for name in names {
+                    let marker = if name == current { "*" } else { " " };
+                    println!("{} {}", marker, name);
+                }
+            }
+            Some(("create", create_matches)) => {
+                let name = create_matches.get_one::<String>("name").unwrap();
+                if let Err(e) = wallets::select(name) {
+                    error(&e);
+                    return;
+                }
+                create(create_matches);
+            }
+            Some(("switch", switch_matches)) => {
+                let name = switch_matches.get_one::<String>("name").unwrap();
+                match wallets::exists(name) {
+                    Ok(true) => match wallets::set_current(name) {
+                        Ok(()) => message(&format!("Switched to wallet {}.", name)),
+                        Err(e) => error(&e),
+                    },
+                    Ok(false) => error(&format!("No wallet named {}.", name)),
+                    Err(e) => error(&e),
+                }
+            }
+            Some(("delete", delete_matches)) => {
+                let name = delete_matches.get_one::<String>("name").unwrap();
+                delete_wallet(name);
+            }
+            _ => error("COULD NOT FIND MATCHES. Try swappy help."),
+        },
+        Some(("utxos", utxos_matches)) => {
+            let wallet_info = or_exit!(get_wallet_info());
+            let wallet = or_exit!(init_public_wallet(&wallet_info));
+            let db_path = or_exit!(get_db_path());
+            match utxos_matches.subcommand() {
+                Some(("list", _)) => match list_utxos(&wallet, &wallet_info, &db_path) {
+                    Ok(utxos) => print_utxos(utxos),
+                    Err(e) => error(&e),
+                },
+                Some((command @ ("freeze" | "unfreeze"), freeze_matches)) => {
+                    let frozen = command == "freeze";
+                    for outpoint in freeze_matches.get_many::<OutPoint>("outpoint").unwrap() {
+                        if let Err(e) =
+                            update_utxo(&wallet, &db_path, outpoint, |utxo| utxo.frozen = frozen)
+                        {
+                            error(&e);
+                            return;
+                        }
+                    }
+                    message(if frozen {
+                        "Coins frozen."
+                    } else {
+                        "Coins unfrozen."
+                    });
+                }
+                Some(("label", label_matches)) => {
+                    let outpoint = label_matches.get_one::<OutPoint>("outpoint").unwrap();
+                    let label = label_matches.get_one::<String>("label").cloned();
+                    match update_utxo(&wallet, &db_path, outpoint, |utxo| utxo.label = label) {
+                        Ok(()) => message("Label saved."),
+                        Err(e) => error(&e),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(("psbt", psbt_matches)) => {
+            match psbt_matches.subcommand() {
+                Some(("create", create_matches)) => {
+                    let wallet_info = or_exit!(get_wallet_info());
+                    let address = create_matches.get_one::<String>("address").unwrap();
+                    let address = match Address::from_str(address) {
+                        Ok(address) => address,
+                        Err(e) => {
+                            error(&format!("Invalid address: {}", e));
+                            return;
+                        }
+                    };
+                    let btc_amount = create_matches.get_one::<f64>("amount").copied();
+                    let fee_rate = match get_fee_rate(&wallet_info, create_matches) {
+                        Ok(fee_rate) => fee_rate,
+                        Err(e) => {
+                            error(&e);
+                            return;
+                        }
+                    };
+                    let coins = match read_coin_control(create_matches) {
+                        Ok(coins) => coins,
+                        Err(e) => {
+                            error(&e);
+                            return;
+                        }
+                    };
+                    let wallet = or_exit!(init_public_wallet(&wallet_info));
+                    match build_psbt(&wallet, &address, btc_amount, fee_rate, &coins) {
+                        Ok((psbt, _)) => output_psbt(&psbt, create_matches.get_one("out")),
+                        Err(e) => error(&format!("Error building transaction: {}", e)),
+                    }
+                }
+                Some(("sign", sign_matches)) => {
+                    let mut psbt = match read_psbt_arg(sign_matches) {
+                        Some(psbt) => psbt,
+                        None => return,
+                    };
+                    let wallet_info = or_exit!(get_unlocked_wallet_info());
+                    let wallet = or_exit!(init_secret_wallet(&wallet_info));
+                    let signatures = signature_count(&psbt);
+                    // leave finalizing to `psbt finalize` so other signers can still add signatures
+                    let sign_options = SignOptions {
+                        try_finalize: false,
+                        ..Default::default()
+                    };
+                    if let Err(e) = wallet.sign(&mut psbt, sign_options) {
+                        error(&format!("Error signing PSBT: {}", e));
+                        return;
+                    }
+                    if signature_count(&psbt) == signatures {
+                        error("This wallet has no keys to sign this PSBT with.");
+                        return;
+                    }
+                    output_psbt(&psbt, sign_matches.get_one("out"));
+                }
+                Some(("combine", combine_matches)) => {
+                    let mut psbts = vec![];
+                    for file in combine_matches.get_many::<String>("files").unwrap() {
+                        match read_psbt(Path::new(file)) {
+                            Ok(psbt) => psbts.push(psbt),
+                            Err(e) => {
+                                error(&e);
+                                return;
+                            }
+                        }
+                    }
+                    match combine_psbts(psbts) {
+                        Ok(psbt) => output_psbt(&psbt, combine_matches.get_one("out")),
+                        Err(e) => error(&format!("Error combining PSBTs: {}", e)),
+                    }
+                }
+                Some(("finalize", finalize_matches)) => {
+                    let mut psbt = match read_psbt_arg(finalize_matches) {
+                        Some(psbt) => psbt,
+                        None => return,
+                    };
+                    let wallet_info = or_exit!(get_wallet_info());
+                    let wallet = or_exit!(init_public_wallet(&wallet_info));
+                    match wallet.finalize_psbt(&mut psbt, SignOptions::default()) {
+                        Ok(true) => output_psbt(&psbt, finalize_matches.get_one("out")),
+                        Ok(false) => error("PSBT is missing signatures."),
+                        Err(e) => error(&format!("Error finalizing PSBT: {}", e)),
+                    }
+                }
+                Some(("broadcast", broadcast_matches)) => {
+                    let psbt = match read_psbt_arg(broadcast_matches) {
+                        Some(psbt) => psbt,
+                        None => return,
+                    };
+                    if !is_finalized(&psbt) {
+                        error("PSBT is not finalized. Run swappy psbt finalize first.");
+                        return;
+                    }
+                    let fee = psbt_fee(&psbt);
+                    let tx = psbt.extract_tx();
+                    let wallet_info = or_exit!(get_wallet_info());
+                    let blockchain = or_exit!(wallet_info.electrum_blockchain());
+                    match blockchain.broadcast(&tx) {
+                        Ok(()) => {
+                            if is_json() {
+                                emit(Output::Transaction {
+                                    txid: tx.txid().to_string(),
+                                    fee,
+                                });
+                            } else {
+                                println!("Broadcast transaction: {}", tx.txid());
+                            }
+                        }
+                        Err(e) => error(&format!("Error broadcasting transaction: {}", e)),
+                    }
+                }
+                Some(("decode", decode_matches)) => {
+                    let psbt = match read_psbt_arg(decode_matches) {
+                        Some(psbt) => psbt,
+                        None => return,
+                    };
+                    // addresses use the wallet's network when there is one, decoding needs no wallet
+                    let network = get_db_path()
+                        .and_then(|path| read_db(&path))
+                        .map_or(bdk::bitcoin::Network::Bitcoin, |wallet_info| {
+                            wallet_info.network
+                        });
+                    print_psbt(PsbtInfo::new(&psbt, network));
+                }
+                _ => error("COULD NOT FIND MATCHES. Try swappy help."),
+            }
+        }
+        Some(("swaps", swaps_matches)) => {
+            let db_path = or_exit!(get_db_path());
+            match swaps_matches.subcommand() {
+                Some(("list", _)) => {
+                    let swaps = or_exit!(read_swaps(&db_path));
+                    if is_json() {
+                        emit(Output::Swaps {
+                            swaps: swaps.iter().map(SwapInfo::from).collect(),
+                        });
+                        return;
+                    }
+                    for swap in swaps {
+                        println!("x------------------------x");
+                        println!("Id: {}", swap.id);
+                        println!("Kind: {:?}", swap.kind);
+                        println!("State: {:?}", swap.state);
+                        println!("Amount: {}", swap.amount);
+                        println!("Boltz Status: {:?}", swap.boltz_status);
+                    }
+                }
+                Some(("resume", _)) => {
+                    let wallet_info = or_exit!(get_unlocked_wallet_info());
+                    let resumed = resume_swaps(&wallet_info, &db_path);
+                    if is_json() {
+                        emit(Output::Swaps {
+                            swaps: or_exit!(read_swaps(&db_path))
+                                .iter()
+                                .map(SwapInfo::from)
+                                .collect(),
+                        });
+                    }
+                    match resumed {
+                        Ok(failed) if failed.is_empty() => {
+                            info("All swaps up to date.");
+                        }
+                        Ok(failed) => {
+                            error(&format!("Failed to resume {} swap(s).", failed.len()));
+                            std::process::exit(1);
                         }
                         Err(e) => {
-                            println!("Could not resolve input to invoice");
+                            error(&format!("Error resuming swaps: {}", e));
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                Some(("refund", refund_matches)) => {
+                    let wallet_info = or_exit!(get_unlocked_wallet_info());
+                    let id = refund_matches.get_one::<String>("id").unwrap();
+                    let mut swap = or_exit!(read_swap(&db_path, id));
+                    if let Err(e) = check_refundable(&swap) {
+                        error(&e);
+                        return;
+                    }
+                    if let Some(address) = refund_matches.get_one::<String>("address") {
+                        if let Err(e) = Address::from_str(address) {
+                            error(&format!("Invalid refund address: {}", e));
+                            return;
+                        }
+                        swap.output_address = address.to_string();
+                        or_exit!(save_swap(&db_path, &swap));
+                    }
+                    loop {
+                        let height = or_exit!(get_tip_height(&wallet_info)) as u64;
+                        if height >= swap.timeout_block_height {
+                            break;
+                        }
+                        if !refund_matches.get_flag("wait") {
+                            error(&format!(
+                                "Swap {} can be refunded after block {} (current {}).",
+                                swap.id, swap.timeout_block_height, height
+                            ));
                             return;
                         }
+                        info(&format!(
+                            "Waiting for block {} (current {})...",
+                            swap.timeout_block_height, height
+                        ));
+                        thread::sleep(Duration::from_secs(60));
+                    }
+                    match refund_swap(swap, &wallet_info, &db_path) {
+                        Ok(swap) => {
+                            if is_json() {
+                                emit(Output::Swap(SwapInfo::from(&swap)));
+                            } else {
+                                println!("Refund broadcast: {}", swap.spend_txid.unwrap());
+                            }
+                        }
+                        Err(e) => {
+                            error(&format!("Error refunding swap: {}", e));
+                        }
                     }
                 }
+                _ => error("COULD NOT FIND MATCHES. Try swappy help."),
             }
         }
-        None => {
-            println!("COULD NOT FIND MATCHES. Try swappy help.")
+        None => error("COULD NOT FIND MATCHES. Try swappy help."),
+        _ => error("COULD NOT FIND MATCHES. Try swappy help."),
+    }
+}
+
+fn create(arg_matches: &clap::ArgMatches) {
+    let path = match get_db_path() {
+        Ok(path) => path,
+        Err(e) => {
+            error(&e);
+            return;
+        }
+    };
+    let already_exists = path.exists();
+    if already_exists {
+        error("Wallet already exists. Retry after swappy delete.");
+        return;
+    }
+
+    let mut wallet_info = NetworkInfoModel::from_arg_matches(arg_matches.clone());
+    if let Some(public) = arg_matches.get_one::<String>("watch-only") {
+        let script_type = arg_matches.get_one::<ScriptType>("script-type").copied();
+        let descriptors = if public.contains('(') {
+            match script_type {
+                Some(_) => Err("--script-type only applies to xpubs.".to_string()),
+                None => Descriptors::from_descriptor(public),
+            }
+        } else {
+            Descriptors::from_xpub(public, wallet_info.network, script_type)
+        };
+        let response = descriptors
+            .and_then(|descriptors| wallet_info.update_watch_only(descriptors).map(|_| ()))
+            .and_then(|_| create_db(&wallet_info, &path, ""));
+        match response {
+            Ok(()) => message("Successsfully created new watch-only wallet."),
+            Err(e) => error(&e),
+        }
+        return;
+    }
+    let bip39_passphrase = match read_bip39_passphrase(arg_matches, true) {
+        Ok(bip39_passphrase) => bip39_passphrase,
+        Err(e) => {
+            error(&e);
+            return;
+        }
+    };
+    wallet_info.multisig = match read_multisig(arg_matches, wallet_info.network) {
+        Ok(multisig) => multisig,
+        Err(e) => {
+            error(&e);
+            return;
         }
-        _ => {
-            println!("COULD NOT FIND MATCHES. Try swappy help.")
+    };
+    let mnemonic = wallet::util::create_mnemonic();
+    if let Err(e) = wallet_info.update_mnemonic(mnemonic.clone(), bip39_passphrase) {
+        error(&e);
+        return;
+    }
+    reveal_seed(&mnemonic);
+    let confirmation =
+        prompt("Have you written down and secured your mnemonic? Type 'yes' to confirm:");
+    if confirmation != "yes" {
+        error("Backup not confirmed. Exiting.");
+        return;
+    }
+    let passphrase = match new_passphrase() {
+        Ok(passphrase) => passphrase,
+        Err(e) => {
+            error(&e);
+            return;
+        }
+    };
+
+    let response = create_db(&wallet_info, &path, &passphrase);
+    match response {
+        Ok(()) => {
+            message("Successsfully created new wallet.");
+            if wallet_info.multisig.is_some() {
+                show_multisig_xpub(&wallet_info);
+            }
         }
+        Err(e) => error(&e),
     }
 }
 
-fn get_wallet_info() -> Result<NetworkInfoModel, String> {
-    let mut root_path: PathBuf = match std::env::var("HOME") {
-        Ok(home_path) => {
-            let mut full_path = PathBuf::from(home_path);
-            full_path.push(SWAPPY_DIR);
-            full_path
+fn delete_wallet(name: &str) {
+    match wallets::exists(name) {
+        Ok(true) => {}
+        Ok(false) => {
+            error(&format!("No wallet named {}.", name));
+            return;
         }
         Err(e) => {
-            return Err(e.to_string());
+            error(&e);
+            return;
         }
+    }
+    let confirmation = prompt(&format!(
+        "DELETING WALLET {}! CAREFUL! ARE YOU SURE? Type 'yes' to confirm.",
+        name
+    ));
+    if confirmation != "yes" {
+        error("Aborting delete.");
+        return;
+    }
+    if let Err(e) = wallets::delete(name) {
+        error(&format!("Failed to delete wallet {}: {}", name, e));
+    } else {
+        message("Wallet successfully deleted.");
+    }
+}
+
+/// The only place the mnemonic is printed: once on create and on backup show-seed.
+fn reveal_seed(mnemonic: &str) {
+    if is_json() {
+        emit(Output::Seed {
+            mnemonic: mnemonic.to_string(),
+        });
+    } else {
+        println!("Your mnemonic is: {}", mnemonic);
+    }
+}
+
+/// Shared by create and wallets create.
+fn create_command() -> Command {
+    Command::new("create")
+        .about("create a wallet with network settings ")
+        .display_order(1)
+        .args(network_args())
+        .args(seed_args())
+        .args(multisig_args())
+        .arg(
+            Arg::new("watch-only")
+                .long("watch-only")
+                .value_name("XPUB|DESCRIPTOR")
+                .help("create a watch-only wallet from an xpub/tpub/zpub/vpub or a descriptor")
+                .conflicts_with_all(["bip39-passphrase", "threshold"]),
+        )
+}
+
+/// Server and network settings shared by create and restore.
+fn network_args() -> [Arg; 6] {
+    [
+        Arg::new("electrum")
+            .short('e')
+            .long("electrum")
+            .help("electrum server url")
+            .required(true),
+        Arg::new("boltz")
+            .short('b')
+            .long("boltz")
+            .help("boltz server url")
+            .required(true),
+        Arg::new("network")
+            .short('n')
+            .long("network")
+            .help("mainnet, testnet, signet or regtest")
+            .value_parser(parse_network)
+            .default_value("testnet"),
+        Arg::new("electrum-tls")
+            .long("electrum-tls")
+            .help("connect to electrum over tls (default: true, false on regtest)")
+            .value_parser(clap::value_parser!(bool)),
+        Arg::new("electrum-validate-domain")
+            .long("electrum-validate-domain")
+            .help("validate the electrum tls certificate (default: same as tls)")
+            .value_parser(clap::value_parser!(bool)),
+        Arg::new("electrum-timeout")
+            .long("electrum-timeout")
+            .help("electrum timeout in seconds")
+            .value_parser(clap::value_parser!(u8))
+            .default_value("10"),
+    ]
+}
+
+/// Multisig policy shared by create and restore. The local seed is one of the signers.
+fn multisig_args() -> [Arg; 2] {
+    [
+        Arg::new("threshold")
+            .long("threshold")
+            .help("signatures needed to spend from a wsh(sortedmulti) multisig wallet")
+            .value_parser(clap::value_parser!(usize))
+            .requires("cosigner")
+            .conflicts_with("script-type"),
+        Arg::new("cosigner")
+            .long("cosigner")
+            .help("a cosigner's key from swappy backup show-xpub, repeat for each cosigner")
+            .action(clap::ArgAction::Append)
+            .requires("threshold"),
+    ]
+}
+
+fn read_multisig(
+    arg_matches: &clap::ArgMatches,
+    network: bdk::bitcoin::Network,
+) -> Result<Option<Multisig>, String> {
+    match arg_matches.get_one::<usize>("threshold") {
+        Some(threshold) => {
+            let cosigners: Vec<String> = arg_matches
+                .get_many::<String>("cosigner")
+                .unwrap()
+                .cloned()
+                .collect();
+            Multisig::new(*threshold, &cosigners, network).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+fn show_multisig_xpub(wallet_info: &NetworkInfoModel) {
+    match multisig_xpub(
+        &wallet_info.display_secret(),
+        &wallet_info.bip39_passphrase(),
+        wallet_info.network,
+    ) {
+        Ok(xpub) => message(&format!("Your multisig key for cosigners is: {}", xpub)),
+        Err(e) => error(&e),
+    }
+}
+
+/// Seed derivation settings shared by create and restore.
+fn seed_args() -> [Arg; 3] {
+    [
+        Arg::new("script-type")
+            .long("script-type")
+            .help("wpkh (BIP84, default), tr (BIP86), sh-wpkh (BIP49) or pkh (BIP44)")
+            .value_parser(ScriptType::from_str),
+        Arg::new("bip39-passphrase")
+            .long("bip39-passphrase")
+            .help("ask for a bip39 passphrase (25th word) to derive the wallet with")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("bip39-passphrase-policy")
+            .long("bip39-passphrase-policy")
+            .help("store the bip39 passphrase encrypted with the seed, or prompt for it on every unlock")
+            .value_parser(["store", "prompt"])
+            .default_value("store")
+            .requires("bip39-passphrase"),
+    ]
+}
+
+/// Fee selection shared by send and psbt create.
+fn fee_args() -> [Arg; 2] {
+    [
+        Arg::new("priority")
+            .long("priority")
+            .help("confirmation speed to estimate the fee for")
+            .value_parser(Priority::from_str)
+            .default_value("normal"),
+        Arg::new("fee-rate")
+            .long("fee-rate")
+            .help("fee rate in sat/vB instead of an estimate")
+            .value_parser(clap::value_parser!(f32))
+            .conflicts_with("priority"),
+    ]
+}
+
+/// The --fee-rate, or the estimate for --priority, within the wallet's fee_floor and fee_ceiling.
+fn get_fee_rate(
+    wallet_info: &NetworkInfoModel,
+    arg_matches: &clap::ArgMatches,
+) -> Result<FeeRate, String> {
+    let choice = match arg_matches.get_one::<f32>("fee-rate") {
+        Some(rate) => FeeChoice::Rate(*rate),
+        None => FeeChoice::Priority(*arg_matches.get_one::<Priority>("priority").unwrap()),
     };
-    let wallet_info = read_db(&root_path)?;
-    Ok(wallet_info)
+    resolve_fee_rate(
+        choice,
+        &wallet_info.electrum_blockchain()?,
+        wallet_info.fee_floor,
+        wallet_info.fee_ceiling,
+    )
 }
-fn init_public_wallet(wallet_info: &NetworkInfoModel) -> Result<Wallet<SqliteDatabase>, String> {
-    let descriptors = Descriptors::new_public(&wallet_info.display_secret())?;
-    let sqlite_path: PathBuf = match std::env::var("HOME") {
-        Ok(home_path) => {
-            let mut full_path = PathBuf::from(home_path);
-            full_path.push("bdk");
-            full_path
+
+/// Coin selection shared by send and psbt create.
+fn coin_args() -> [Arg; 3] {
+    [
+        Arg::new("utxo")
+            .long("utxo")
+            .help("spend only this coin (txid:vout), repeatable")
+            .value_parser(OutPoint::from_str)
+            .action(clap::ArgAction::Append),
+        Arg::new("max")
+            .long("max")
+            .help("send all spendable coins minus fees; send chain only, an ln invoice fixes the amount")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("confirmed-only")
+            .long("confirmed-only")
+            .help("leave unconfirmed coins alone")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+fn outpoint_arg() -> Arg {
+    Arg::new("outpoint")
+        .help("coin as txid:vout")
+        .value_parser(OutPoint::from_str)
+        .required(true)
+}
+
+/// Arguments shared by bump and cpfp.
+fn accelerate_args(txid_help: &'static str) -> [Arg; 3] {
+    [
+        Arg::new("txid").help(txid_help).required(true),
+        Arg::new("fee-rate")
+            .long("fee-rate")
+            .help("target fee rate in sat/vB")
+            .value_parser(clap::value_parser!(f32))
+            .required(true),
+        Arg::new("yes")
+            .short('y')
+            .long("yes")
+            .help("accept the new fee without asking")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+fn psbt_file_arg() -> Arg {
+    Arg::new("file").help("base64 PSBT file").required(true)
+}
+
+fn psbt_out_arg() -> Arg {
+    Arg::new("out")
+        .short('o')
+        .long("out")
+        .help("write the PSBT to this file instead of printing it")
+}
+
+fn read_psbt_arg(arg_matches: &clap::ArgMatches) -> Option<PartiallySignedTransaction> {
+    let file = arg_matches.get_one::<String>("file").unwrap();
+    match read_psbt(Path::new(file)) {
+        Ok(psbt) => Some(psbt),
+        Err(e) => {
+            error(&e);
+            None
+        }
+    }
+}
+
+/// Writes `psbt` to the `--out` file, or prints it as base64.
+fn output_psbt(psbt: &PartiallySignedTransaction, out: Option<&String>) {
+    match out {
+        Some(path) => match write_psbt(psbt, Path::new(path)) {
+            Ok(()) => message(&format!("PSBT written to {}", path)),
+            Err(e) => error(&e),
+        },
+        None if is_json() => emit(Output::Psbt {
+            psbt: psbt.to_string(),
+            fee: psbt_fee(psbt),
+        }),
+        None => println!("{}", psbt),
+    }
+}
+
+fn print_psbt(psbt: PsbtInfo) {
+    if is_json() {
+        emit(Output::PsbtInfo(psbt));
+        return;
+    }
+    println!("Txid: {}", psbt.txid);
+    println!("Inputs:");
+    for input in &psbt.inputs {
+        let value = match input.value {
+            Some(value) => format!("{} sats", value),
+            None => "unknown amount".to_string(),
+        };
+        let status = if input.finalized {
+            "finalized".to_string()
+        } else {
+            format!("{} signature(s)", input.signatures)
+        };
+        println!("  {} {}, {}", input.outpoint, value, status);
+    }
+    println!("Outputs:");
+    for output in &psbt.outputs {
+        let address = output.address.as_deref().unwrap_or("<no address>");
+        println!("  {} {} sats", address, output.value);
+    }
+    match psbt.fee {
+        Some(fee) => println!("Fee: {} sats", fee),
+        None => println!("Fee: unknown"),
+    }
+}
+
+fn list_utxos(
+    wallet: &Wallet<SqliteDatabase>,
+    wallet_info: &NetworkInfoModel,
+    db_path: &Path,
+) -> Result<Vec<UtxoInfo>, String> {
+    let tip = get_tip_height(wallet_info)?;
+    let saved = read_utxos(db_path)?;
+    let mut utxos = vec![];
+    for utxo in wallet.list_unspent().map_err(|e| e.to_string())? {
+        let height = wallet
+            .get_tx(&utxo.outpoint.txid, false)
+            .map_err(|e| e.to_string())?
+            .and_then(|tx| tx.confirmation_time)
+            .map(|time| time.height);
+        let saved = saved.get(&utxo.outpoint).cloned().unwrap_or_default();
+        utxos.push(UtxoInfo {
+            outpoint: utxo.outpoint.to_string(),
+            amount: utxo.txout.value,
+            address: Address::from_script(&utxo.txout.script_pubkey, wallet_info.network)
+                .ok()
+                .map(|address| address.to_string()),
+            confirmations: height.map_or(0, |height| tip.saturating_sub(height) + 1),
+            label: saved.label,
+            frozen: saved.frozen,
+        });
+    }
+    Ok(utxos)
+}
+
+fn print_utxos(utxos: Vec<UtxoInfo>) {
+    if is_json() {
+        emit(Output::Utxos { utxos });
+        return;
+    }
+    for utxo in utxos {
+        println!("Outpoint: {}", utxo.outpoint);
+        println!("Amount: {} sats", utxo.amount);
+        if let Some(address) = utxo.address {
+            println!("Address: {}", address);
+        }
+        println!("Confirmations: {}", utxo.confirmations);
+        if let Some(label) = utxo.label {
+            println!("Label: {}", label);
+        }
+        if utxo.frozen {
+            println!("Frozen");
+        }
+        println!("x------------------------x");
+    }
+}
+
+/// Changes the coin control state of one of our unspent outputs.
+fn update_utxo(
+    wallet: &Wallet<SqliteDatabase>,
+    db_path: &Path,
+    outpoint: &OutPoint,
+    update: impl FnOnce(&mut UtxoModel),
+) -> Result<(), String> {
+    if wallet
+        .get_utxo(*outpoint)
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!(
+            "{} is not an unspent coin of this wallet.",
+            outpoint
+        ));
+    }
+    let mut utxo = read_utxos(db_path)?.remove(outpoint).unwrap_or_default();
+    update(&mut utxo);
+    save_utxo(db_path, outpoint, &utxo)
+}
+
+/// Reads one trimmed line from stdin after printing `message`.
+fn prompt(message: &str) -> String {
+    info(message);
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    input.trim().to_string()
+}
+
+fn receive_chain(wallet: &Wallet<SqliteDatabase>) {
+    let address = or_exit!(wallet.get_address(LastUnused).map_err(|e| e.to_string()));
+    if is_json() {
+        emit(Output::Address {
+            address: address.address.to_string(),
+        });
+    } else {
+        println!("{}", address.address);
+    }
+}
+
+fn receive_ln(
+    wallet: &Wallet<SqliteDatabase>,
+    wallet_info: &NetworkInfoModel,
+    out_amount: u64,
+    yes: bool,
+) {
+    let pair = match get_pair_info(wallet_info) {
+        Ok(pair) => pair,
+        Err(e) => {
+            error(&format!("Error fetching boltz fees: {}", e));
+            return;
+        }
+    };
+    let quote = match pair.quote(SwapKind::Reverse, out_amount) {
+        Ok(quote) => quote,
+        Err(e) => {
+            error(&e);
+            return;
+        }
+    };
+    if !confirm_quote(&quote, yes) {
+        error("Swap not confirmed. Exiting.");
+        return;
+    }
+    let claim_address = or_exit!(wallet.get_address(LastUnused).map_err(|e| e.to_string())).address;
+    let db_path = or_exit!(get_db_path());
+    // construct SwapScript
+    match create_reverse_submarine_swap(
+        &out_amount,
+        &claim_address.to_string(),
+        &pair,
+        &quote,
+        wallet_info,
+        &db_path,
+    ) {
+        Ok(mut swap) => {
+            if is_json() {
+                emit(Output::Invoice {
+                    swap_id: swap.id.clone(),
+                    invoice: swap.invoice.clone(),
+                    amount: out_amount,
+                });
+            } else {
+                println!("Complete payment of LN to :{}", swap.invoice);
+            }
+            // wait till someone pays the invoice, then claim to our wallet
+            let start = Instant::now();
+            loop {
+                swap = match advance_swap(swap.clone(), wallet_info, &db_path) {
+                    Ok(swap) => swap,
+                    Err(e) => {
+                        eprintln!("Error updating swap {}: {}", swap.id, e);
+                        swap
+                    }
+                };
+                if swap.state == SwapState::Claimed {
+                    info("Received payment from boltz");
+                    info(&swap.spend_txid.clone().unwrap());
+                    break;
+                } else if swap.state.is_final() {
+                    info(&format!("Swap {} ended as {:?}.", swap.id, swap.state));
+                    break;
+                } else if start.elapsed() > Duration::from_secs(60) {
+                    info(&format!(
+                        "Stopped waiting for payment. Swap {} is saved, run swappy swaps resume to claim it.",
+                        swap.id
+                    ));
+                    break;
+                } else {
+                    eprintln!("No payment yet...");
+                    thread::sleep(Duration::from_secs(10));
+                }
+            }
+            if is_json() {
+                emit(Output::Swap(SwapInfo::from(&swap)));
+            }
         }
         Err(e) => {
-            return Err(e.to_string());
+            error(&format!("Error creating reverse swap: {}", e));
         }
     };
-    create_wallet(descriptors, &sqlite_path)
 }
-fn init_secret_wallet(wallet_info: &NetworkInfoModel) -> Result<Wallet<SqliteDatabase>, String> {
-    let descriptors = Descriptors::new_secret(&wallet_info.display_secret())?;
-    let sqlite_path: PathBuf = match std::env::var("HOME") {
-        Ok(home_path) => {
-            let mut full_path = PathBuf::from(home_path);
-            full_path.push("bdk");
-            full_path
+
+fn send_chain(
+    wallet: &Wallet<SqliteDatabase>,
+    wallet_info: &NetworkInfoModel,
+    address: &Address,
+    btc_amount: Option<f64>,
+    fee_rate: FeeRate,
+    coins: &CoinControl,
+    yes: bool,
+) {
+    if wallet_info.watch_only || wallet_info.needs_cosigners() {
+        let built = if wallet_info.watch_only {
+            build_psbt(wallet, address, btc_amount, fee_rate, coins)
+        } else {
+            cosign_psbt(wallet, address, btc_amount, fee_rate, coins)
+        };
+        match built {
+            Ok((psbt, details)) => {
+                if is_json() {
+                    emit(Output::Psbt {
+                        psbt: psbt.to_string(),
+                        fee: details.fee,
+                    });
+                } else if wallet_info.watch_only {
+                    println!("{}", describe_transaction(&details, address));
+                    println!("Unsigned PSBT, sign it with swappy psbt sign:\n{}", psbt);
+                } else {
+                    println!("{}", describe_transaction(&details, address));
+                    println!(
+                        "Partially signed PSBT, pass it to your cosigners for swappy psbt sign:\n{}",
+                        psbt
+                    );
+                }
+            }
+            Err(e) => error(&format!("Error building transaction: {}", e)),
         }
+        return;
+    }
+    let blockchain = or_exit!(wallet_info.electrum_blockchain());
+    match send_btc(
+        wallet,
+        address,
+        btc_amount,
+        fee_rate,
+        coins,
+        &blockchain,
+        yes,
+    ) {
+        Ok((transaction, fee)) => {
+            if is_json() {
+                emit(Output::Transaction {
+                    txid: transaction.txid().to_string(),
+                    fee,
+                });
+            } else {
+                println!("Payment successful: {}", transaction.txid());
+            }
+        }
+        Err(e) => error(&format!("Error in payment: {}", e)),
+    };
+}
+
+fn send_ln(
+    wallet: &Wallet<SqliteDatabase>,
+    wallet_info: &NetworkInfoModel,
+    invoice: &Bolt11Invoice,
+    fee_rate: FeeRate,
+    coins: &CoinControl,
+    yes: bool,
+) {
+    if wallet_info.watch_only {
+        error("Watch-only wallets cannot fund swaps.");
+        return;
+    }
+    if coins.spend_all {
+        error("--max only works with send chain: the invoice fixes the swap amount.");
+        return;
+    }
+    let invoice_amount = match invoice.amount_milli_satoshis() {
+        Some(msats) => msats / 1000,
+        None => {
+            error("Invoice has no amount.");
+            return;
+        }
+    };
+    let pair = match get_pair_info(wallet_info) {
+        Ok(pair) => pair,
         Err(e) => {
-            return Err(e.to_string());
+            error(&format!("Error fetching boltz fees: {}", e));
+            return;
+        }
+    };
+    let quote = match pair.quote(SwapKind::Submarine, invoice_amount) {
+        Ok(quote) => quote,
+        Err(e) => {
+            error(&e);
+            return;
+        }
+    };
+    if !confirm_quote(&quote, yes) {
+        error("Swap not confirmed. Exiting.");
+        return;
+    }
+
+    let refund_address =
+        or_exit!(wallet.get_address(LastUnused).map_err(|e| e.to_string())).address;
+    let db_path = or_exit!(get_db_path());
+    match create_submarine_swap(
+        &invoice.to_string(),
+        &refund_address.to_string(),
+        &pair,
+        wallet_info,
+        &db_path,
+    ) {
+        Ok(mut swap) if swap.amount > quote.amount_sent => {
+            // never funded, so nothing to refund
+            swap.state = SwapState::Failed;
+            if let Err(e) = save_swap(&db_path, &swap) {
+                eprintln!("Error saving swap {}: {}", swap.id, e);
+            }
+            error(&format!(
+                "Boltz asked for {} sats, more than the quoted {}. Not funding swap {}.",
+                swap.amount, quote.amount_sent, swap.id
+            ));
         }
+        Ok(mut swap) => {
+            let funding_amount = Some(Amount::from_sat(swap.amount).to_btc());
+            let lockup_address =
+                or_exit!(Address::from_str(&swap.lockup_address).map_err(|e| e.to_string()));
+            if wallet_info.needs_cosigners() {
+                // cosigners sign and broadcast the lockup, swaps resume picks it up from boltz
+                match cosign_psbt(wallet, &lockup_address, funding_amount, fee_rate, coins) {
+                    Ok((psbt, _)) => {
+                        if is_json() {
+                            emit(Output::Swap(SwapInfo::from(&swap)));
+                        }
+                        output_psbt(&psbt, None);
+                        info(&format!(
+                            "Swap {} is funded once your cosigners sign and broadcast this PSBT before block {}. Then run swappy swaps resume.",
+                            swap.id, swap.timeout_block_height
+                        ));
+                    }
+                    Err(e) => error(&format!("Error funding swap: {}", e)),
+                }
+                return;
+            }
+            // the quote only covers boltz's fees, the lockup's own miner fee is confirmed separately
+            match send_btc(
+                wallet,
+                &lockup_address,
+                funding_amount,
+                fee_rate,
+                coins,
+                &or_exit!(wallet_info.electrum_blockchain()),
+                yes,
+            ) {
+                Ok((transaction, _)) => {
+                    swap.state = SwapState::Funded;
+                    swap.lockup_txid = Some(transaction.txid().to_string());
+                    if let Err(e) = save_swap(&db_path, &swap) {
+                        eprintln!("Error saving swap {}: {}", swap.id, e);
+                    }
+                    if is_json() {
+                        emit(Output::Swap(SwapInfo::from(&swap)));
+                    } else {
+                        println!("Swap {} funded by {}", swap.id, transaction.txid());
+                        println!("Invoice will be paid after 1 conf.")
+                    }
+                    //check if boltz paid LN addr?
+                }
+                Err(e) => {
+                    error(&format!("Error funding swap: {}", e));
+                }
+            };
+        }
+        Err(e) => error(&format!("Error creating submarine swap: {}", e)),
     };
-    create_wallet(descriptors, &sqlite_path)
 }
 
-fn send_btc(
+/// Shows a swap quote and asks the user to accept it, unless they already did with --yes.
+fn confirm_quote(quote: &SwapQuote, yes: bool) -> bool {
+    if is_json() {
+        emit(Output::Quote(quote.clone()));
+    } else {
+        print_quote(quote);
+    }
+    if yes {
+        return true;
+    }
+    prompt("Accept this quote? Type 'yes' to confirm:") == "yes"
+}
+
+fn print_quote(quote: &SwapQuote) {
+    println!("x------------------------x");
+    println!("Swap Quote ({:?})", quote.kind);
+    println!("You send: {} sats", quote.amount_sent);
+    println!("You receive: {} sats", quote.amount_received);
+    println!("Boltz fee: {} sats", quote.boltz_fee);
+    println!("Miner fees: {} sats", quote.miner_fees);
+    println!("Total cost: {} sats", quote.total_cost);
+    println!("x------------------------x");
+}
+
+/// Sled db of the wallet picked with --wallet or wallets switch.
+fn get_db_path() -> Result<PathBuf, String> {
+    wallets::db_path(&wallets::current()?)
+}
+
+/// The wallet passphrase from SWAPPY_PASSPHRASE, or asked for without echo.
+fn read_passphrase(message: &str) -> Result<String, String> {
+    read_secret("SWAPPY_PASSPHRASE", message)
+}
+
+/// A secret from env_var if set, otherwise asked for without echo.
+fn read_secret(env_var: &str, message: &str) -> Result<String, String> {
+    match std::env::var(env_var) {
+        Ok(secret) => Ok(secret),
+        Err(_) => rpassword::prompt_password(message).map_err(|e| e.to_string()),
+    }
+}
+
+fn new_passphrase() -> Result<String, String> {
+    let passphrase = read_passphrase("Choose a passphrase to encrypt your seed: ")?;
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty.".to_string());
+    }
+    if std::env::var("SWAPPY_PASSPHRASE").is_err()
+        && read_passphrase("Repeat passphrase: ")? != passphrase
+    {
+        return Err("Passphrases do not match.".to_string());
+    }
+    Ok(passphrase)
+}
+
+/// The BIP39 passphrase asked for with --bip39-passphrase; new ones are typed twice.
+fn read_bip39_passphrase(
+    arg_matches: &clap::ArgMatches,
+    new: bool,
+) -> Result<Option<String>, String> {
+    if !arg_matches.get_flag("bip39-passphrase") {
+        return Ok(None);
+    }
+    let bip39_passphrase = read_secret("SWAPPY_BIP39_PASSPHRASE", "Enter bip39 passphrase: ")?;
+    if new
+        && std::env::var("SWAPPY_BIP39_PASSPHRASE").is_err()
+        && read_secret("SWAPPY_BIP39_PASSPHRASE", "Repeat bip39 passphrase: ")? != bip39_passphrase
+    {
+        return Err("BIP39 passphrases do not match.".to_string());
+    }
+    Ok(Some(bip39_passphrase))
+}
+
+fn unlock_wallet(wallet_info: &mut NetworkInfoModel) -> Result<(), String> {
+    if wallet_info.watch_only {
+        return Err("This is a watch-only wallet; it has no seed.".to_string());
+    }
+    if wallet_info.has_plaintext_seed() {
+        info("Your seed is stored unencrypted. Choose a passphrase to encrypt it.");
+        let passphrase = new_passphrase()?;
+        let root_path = get_db_path()?;
+        encrypt_plaintext_seed(&root_path, wallet_info, &passphrase)?;
+        *wallet_info = read_db(&root_path)?;
+        wallet_info.unlock(&passphrase)?;
+        info("Seed encrypted. The old database files were deleted but not overwritten; wipe free disk space to be sure the plaintext seed is gone.");
+    }
+    if wallet_info.is_locked() {
+        wallet_info.unlock(&read_passphrase("Enter wallet passphrase: ")?)?;
+    }
+    if wallet_info.needs_bip39_passphrase() {
+        wallet_info.set_bip39_passphrase(read_secret(
+            "SWAPPY_BIP39_PASSPHRASE",
+            "Enter bip39 passphrase: ",
+        )?)?;
+    }
+    Ok(())
+}
+
+/// Wallet settings and public descriptors; the seed stays encrypted unless the descriptors
+/// depend on a prompted BIP39 passphrase.
+fn get_wallet_info() -> Result<NetworkInfoModel, String> {
+    let mut wallet_info = read_db(&get_db_path()?)?;
+    if wallet_info.prompt_bip39_passphrase && !wallet_info.watch_only {
+        unlock_wallet(&mut wallet_info)?;
+    } else if wallet_info.has_plaintext_seed() {
+        info("Your seed is stored unencrypted. It will be encrypted the next time it is needed.");
+    }
+    Ok(wallet_info)
+}
+
+/// Wallet settings with the seed decrypted, for signing and swap keys.
+fn get_unlocked_wallet_info() -> Result<NetworkInfoModel, String> {
+    let mut wallet_info = get_wallet_info()?;
+    unlock_wallet(&mut wallet_info)?;
+    Ok(wallet_info)
+}
+
+fn get_sqlite_path(wallet_info: &NetworkInfoModel) -> Result<PathBuf, String> {
+    let name = wallets::current()?;
+    if wallet_info.prompt_bip39_passphrase {
+        return wallets::passphrase_sqlite_path(&name, &wallet_info.deposit_descriptor);
+    }
+    wallets::sqlite_path(&name)
+}
+fn init_public_wallet(wallet_info: &NetworkInfoModel) -> Result<Wallet<SqliteDatabase>, String> {
+    let descriptors = wallet_info.public_descriptors();
+    create_wallet(
+        descriptors,
+        wallet_info.network,
+        &get_sqlite_path(wallet_info)?,
+    )
+}
+fn init_secret_wallet(wallet_info: &NetworkInfoModel) -> Result<Wallet<SqliteDatabase>, String> {
+    if wallet_info.is_locked() {
+        return Err("Wallet is locked.".to_string());
+    }
+    let descriptors = match &wallet_info.multisig {
+        Some(multisig) => Descriptors::new_secret_multisig(
+            &wallet_info.display_secret(),
+            &wallet_info.bip39_passphrase(),
+            wallet_info.network,
+            multisig,
+        )?,
+        None => Descriptors::new_secret(
+            &wallet_info.display_secret(),
+            &wallet_info.bip39_passphrase(),
+            wallet_info.network,
+            wallet_info.script_type,
+        )?,
+    };
+    create_wallet(
+        descriptors,
+        wallet_info.network,
+        &get_sqlite_path(wallet_info)?,
+    )
+}
+
+/// Coins a send must spend (any when empty) and frozen coins it must leave alone.
+struct CoinControl {
+    utxos: Vec<OutPoint>,
+    frozen: Vec<OutPoint>,
+    /// spend every spendable coin (--max)
+    spend_all: bool,
+    confirmed_only: bool,
+}
+
+fn frozen_utxos(db_path: &Path) -> Result<Vec<OutPoint>, String> {
+    Ok(read_utxos(db_path)?
+        .into_iter()
+        .filter(|(_, utxo)| utxo.frozen)
+        .map(|(outpoint, _)| outpoint)
+        .collect())
+}
+
+fn read_coin_control(arg_matches: &clap::ArgMatches) -> Result<CoinControl, String> {
+    let frozen = frozen_utxos(&get_db_path()?)?;
+    let utxos: Vec<OutPoint> = arg_matches
+        .get_many::<OutPoint>("utxo")
+        .map(|utxos| utxos.copied().collect())
+        .unwrap_or_default();
+    if let Some(outpoint) = utxos.iter().find(|outpoint| frozen.contains(outpoint)) {
+        return Err(format!(
+            "{} is frozen. Unfreeze it with swappy utxos unfreeze to spend it.",
+            outpoint
+        ));
+    }
+    Ok(CoinControl {
+        utxos,
+        frozen,
+        spend_all: arg_matches.get_flag("max"),
+        confirmed_only: arg_matches.get_flag("confirmed-only"),
+    })
+}
+
+/// Coins `build_psbt` may spend under `coins`.
+fn spendable_utxos(
+    wallet: &Wallet<SqliteDatabase>,
+    coins: &CoinControl,
+) -> Result<Vec<LocalUtxo>, String> {
+    let mut spendable = vec![];
+    for utxo in wallet.list_unspent().map_err(|e| e.to_string())? {
+        if coins.frozen.contains(&utxo.outpoint)
+            || (!coins.utxos.is_empty() && !coins.utxos.contains(&utxo.outpoint))
+        {
+            continue;
+        }
+        if coins.confirmed_only
+            && wallet
+                .get_tx(&utxo.outpoint.txid, false)
+                .map_err(|e| e.to_string())?
+                .map_or(true, |tx| tx.confirmation_time.is_none())
+        {
+            continue;
+        }
+        spendable.push(utxo);
+    }
+    Ok(spendable)
+}
+
+/// Builds an unsigned transaction paying `amount_btc` to `to_address`, or with no amount
+/// everything spendable minus fees.
+fn build_psbt(
     wallet: &Wallet<bdk::database::SqliteDatabase>,
     to_address: &Address,
-    amount_btc: f64,
-    electrum_url: String,
-) -> Result<Transaction, String> {
-    let amount_sat = Amount::from_btc(amount_btc).unwrap();
+    amount_btc: Option<f64>,
+    fee_rate: FeeRate,
+    coins: &CoinControl,
+) -> Result<(PartiallySignedTransaction, TransactionDetails), String> {
+    let spendable: Vec<OutPoint> = spendable_utxos(wallet, coins)?
+        .iter()
+        .map(|utxo| utxo.outpoint)
+        .collect();
+    let unspendable: Vec<OutPoint> = wallet
+        .list_unspent()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|utxo| utxo.outpoint)
+        .filter(|outpoint| !spendable.contains(outpoint))
+        .collect();
 
     let mut tx_builder = wallet.build_tx();
     tx_builder
-        .add_recipient(to_address.script_pubkey(), amount_sat.to_sat())
         .enable_rbf()
-        .fee_rate(FeeRate::from_sat_per_vb(5.0)); // Example fee rate, adjust as necessary
+        .fee_rate(fee_rate)
+        .unspendable(unspendable);
+    match amount_btc {
+        Some(amount_btc) => {
+            let amount_sat = Amount::from_btc(amount_btc).map_err(|e| e.to_string())?;
+            tx_builder.add_recipient(to_address.script_pubkey(), amount_sat.to_sat());
+        }
+        None => {
+            tx_builder.drain_to(to_address.script_pubkey());
+        }
+    }
+    if !coins.utxos.is_empty() {
+        tx_builder
+            .add_utxos(&coins.utxos)
+            .map_err(|e| e.to_string())?
+            .manually_selected_only();
+    } else if coins.spend_all {
+        tx_builder.drain_wallet();
+    }
+
+    tx_builder.finish().map_err(|e| e.to_string())
+}
 
-    let (mut psbt, details) = tx_builder.finish().unwrap();
+/// Builds a transaction and adds this seed's signature, leaving the rest to cosigners.
+fn cosign_psbt(
+    wallet: &Wallet<bdk::database::SqliteDatabase>,
+    to_address: &Address,
+    amount_btc: Option<f64>,
+    fee_rate: FeeRate,
+    coins: &CoinControl,
+) -> Result<(PartiallySignedTransaction, TransactionDetails), String> {
+    let (mut psbt, details) = build_psbt(wallet, to_address, amount_btc, fee_rate, coins)?;
+    let sign_options = SignOptions {
+        try_finalize: false,
+        ..Default::default()
+    };
+    wallet
+        .sign(&mut psbt, sign_options)
+        .map_err(|e| e.to_string())?;
+    Ok((psbt, details))
+}
+
+fn send_btc(
+    wallet: &Wallet<bdk::database::SqliteDatabase>,
+    to_address: &Address,
+    amount_btc: Option<f64>,
+    fee_rate: FeeRate,
+    coins: &CoinControl,
+    blockchain: &ElectrumBlockchain,
+    yes: bool,
+) -> Result<(Transaction, Option<u64>), String> {
+    let (mut psbt, details) = build_psbt(wallet, to_address, amount_btc, fee_rate, coins)?;
 
-    // Output the transaction details
-    println!("{:#?}", details);
+    info(&describe_transaction(&details, to_address));
+    confirm_fee(details.fee, fee_rate, yes)?;
 
     // Sign the PSBT
-    wallet.sign(&mut psbt, SignOptions::default()).unwrap();
+    wallet
+        .sign(&mut psbt, SignOptions::default())
+        .map_err(|e| e.to_string())?;
 
     // Extract and broadcast the transaction
     let tx = psbt.extract_tx();
     // Broadcast the transaction using the Electrum client
 
-    let client = Client::new(&electrum_url).unwrap();
-    let blockchain = ElectrumBlockchain::from(client);
-    blockchain.broadcast(&tx).unwrap();
-    return Ok(tx.clone());
-}
-
-fn create_submarine_swap(
-    invoice_str: &str,
-    network_info: &NetworkInfoModel,
-) -> Result<(String, u64), String> {
-    // ensure the payment hash is the one boltz uses in their swap script
-    // SECRETS
-    let mnemonic = network_info.display_secret();
-
-    let keypair =
-        SwapKey::from_submarine_account(&mnemonic.to_string(), "", Chain::BitcoinTestnet, 1)
-            .unwrap()
-            .keypair;
-    println!(
-        "****SECRETS****:\n sec: {:?}, pub: {:?}",
-        keypair.display_secret(),
-        keypair.public_key()
-    );
-    // SECRETS
-    let network_config = ElectrumConfig::default_bitcoin();
-    let _electrum_client = network_config.build_client().unwrap();
-
-    // CHECK FEES AND LIMITS IN BOLTZ AND MAKE SURE USER CONFIRMS THIS FIRST
-    let boltz_client = BoltzApiClient::new(BOLTZ_TESTNET_URL);
-    let boltz_pairs = boltz_client.get_pairs().unwrap();
-    let pair_hash = boltz_pairs
-        .pairs
-        .pairs
-        .get("BTC/BTC")
-        .map(|pair_info| pair_info.hash.clone())
-        .unwrap();
-
-    let request = CreateSwapRequest::new_btc_submarine(
-        pair_hash,
-        invoice_str.to_string(),
-        keypair.public_key().to_string().clone(),
-    );
-    let response = boltz_client.create_swap(request);
-    let preimage_states = Preimage::from_invoice_str(invoice_str).unwrap();
-
-    assert!(response
-        .as_ref()
-        .unwrap()
-        .validate_script_preimage160(preimage_states.clone().hash160));
-
-    println!("{:?}", response);
-    assert!(response.is_ok());
-
-    let timeout = response
-        .as_ref()
-        .unwrap()
-        .timeout_block_height
-        .unwrap()
-        .clone();
-    let _id = response.as_ref().unwrap().id.as_str();
-    let funding_address = response.as_ref().unwrap().address.clone().unwrap();
-    let redeem_script_string = response
-        .as_ref()
-        .unwrap()
-        .redeem_script
-        .as_ref()
-        .unwrap()
-        .clone();
-    //funding_amount is u64. shouldn't it be f64 to repressent 0.00001 BTC?
-    let funding_amount = response
-        .as_ref()
-        .unwrap()
-        .expected_amount
-        .as_ref()
-        .unwrap()
-        .clone();
-
-    let boltz_script = BtcSwapScript::submarine_from_str(&redeem_script_string).unwrap();
-
-    let constructed_script = BtcSwapScript::new(
-        SwapType::Submarine,
-        preimage_states.hash160.to_string(),
-        boltz_script.reciever_pubkey.clone(),
-        timeout as u32,
-        keypair.public_key().to_string().clone(),
-    );
-
-    println!("{:?}", boltz_script);
-
-    assert_eq!(boltz_script, constructed_script);
-
-    println!("{}", funding_address);
-    println!("{}", funding_amount);
-    return Ok((funding_address, funding_amount));
-}
-
-fn create_reverse_submarine_swap(
-    out_amount: &u64,
-    walletInfo: &NetworkInfoModel,
-) -> Result<(String, String, BtcSwapScript, KeyPair, Preimage), String> {
-    // returns invoice to get paid in receive
-    // const RETURN_ADDRESS: &str = "tb1qq20a7gqewc0un9mxxlqyqwn7ut7zjrj9y3d0mu";
-
-    // SECRETS
-    let mnemonic = walletInfo.display_secret();
-
-    let keypair =
-        SwapKey::from_reverse_account(&&mnemonic.to_string(), "", Chain::BitcoinTestnet, 1)
-            .unwrap()
-            .keypair;
-    println!(
-        "****SECRETS****:\n sec: {:?}, pub: {:?}",
-        keypair.display_secret(),
-        keypair.public_key()
-    );
-    let preimage = Preimage::new();
-    println!(
-        "****SECRETS****:\n preimage: {:?}",
-        preimage.to_string().clone()
-    );
-    // SECRETS
-
-    let network_config = ElectrumConfig::default_bitcoin();
-
-    // CHECK FEES AND LIMITS IN BOLTZ AND MAKE SURE USER CONFIRMS THIS FIRST
-    let boltz_client = BoltzApiClient::new(BOLTZ_TESTNET_URL);
-    let boltz_pairs = boltz_client.get_pairs().unwrap();
-    let pair_hash = boltz_pairs
-        .pairs
-        .pairs
-        .get("BTC/BTC")
-        .map(|pair_info| pair_info.hash.clone())
-        .unwrap();
-
-    let request = CreateSwapRequest::new_btc_reverse(
-        pair_hash,
-        preimage.clone().sha256.to_string(),
-        keypair.public_key().to_string().clone(),
-        // timeout as u64,
-        out_amount.clone(),
-    );
-    let response = boltz_client.create_swap(request);
-    println!("{:?}", response);
-    assert!(response.is_ok());
-    assert!(response
-        .as_ref()
-        .unwrap()
-        .validate_invoice_preimage256(preimage.clone().sha256));
-
-    let timeout = response
-        .as_ref()
-        .unwrap()
-        .timeout_block_height
-        .unwrap()
-        .clone();
-    let id = response.as_ref().unwrap().id.as_str();
-    let invoice = response.as_ref().unwrap().invoice.clone().unwrap();
-    let lockup_address = response.as_ref().unwrap().lockup_address.clone().unwrap();
-    let redeem_script_string = response
-        .as_ref()
-        .unwrap()
-        .redeem_script
-        .as_ref()
-        .unwrap()
-        .clone();
-
-    let boltz_rev_script = BtcSwapScript::reverse_from_str(&redeem_script_string).unwrap();
-
-    let constructed_rev_script = BtcSwapScript::new(
-        SwapType::ReverseSubmarine,
-        preimage.hash160.to_string(),
-        keypair.public_key().to_string().clone(),
-        timeout as u32,
-        boltz_rev_script.sender_pubkey.clone(),
-    );
-
-    assert_eq!(constructed_rev_script, boltz_rev_script);
-
-    let constructed_address = constructed_rev_script
-        .to_address(network_config.network())
-        .unwrap();
-    println!("{}", constructed_address.to_string());
-    assert_eq!(constructed_address.to_string(), lockup_address);
-
-    let script_balance = constructed_rev_script
-        .get_balance(network_config.clone())
-        .unwrap();
-    assert_eq!(script_balance.0, 0);
-    assert_eq!(script_balance.1, 0);
-    return Ok((
-        invoice,
-        id.to_string(),
-        constructed_rev_script,
-        keypair,
-        preimage,
-    ));
+    blockchain.broadcast(&tx).map_err(|e| e.to_string())?;
+    return Ok((tx.clone(), details.fee));
 }
 
-fn check_swap_status(id: &String, rev_script: &BtcSwapScript) -> Result<bool, String> {
-    let boltz_client = BoltzApiClient::new(BOLTZ_TESTNET_URL);
-    let network_config = ElectrumConfig::default_bitcoin();
+/// One line with the txid, the amount leaving the wallet and the fee of a built transaction.
+fn describe_transaction(details: &TransactionDetails, to_address: &Address) -> String {
+    let fee = details.fee.unwrap_or(0);
+    format!(
+        "Transaction {}: {} sats to {}, fee {} sats",
+        details.txid,
+        details.sent.saturating_sub(details.received + fee),
+        to_address,
+        fee
+    )
+}
 
-    loop {
-        let request = SwapStatusRequest { id: id.to_string() };
-        let response = boltz_client.swap_status(request);
-        assert!(response.is_ok());
-        let swap_status = response.unwrap().status;
-        println!("SwapStatus: {}", swap_status);
+fn confirm_fee(fee: Option<u64>, fee_rate: FeeRate, yes: bool) -> Result<(), String> {
+    if let Some(fee) = fee {
+        info(&format!(
+            "Fee: {} sats at {:.1} sat/vB",
+            fee,
+            fee_rate.as_sat_per_vb()
+        ));
+    }
+    if !yes && prompt("Sign and broadcast? Type 'yes' to confirm:") != "yes" {
+        return Err("Transaction not confirmed.".to_string());
+    }
+    Ok(())
+}
 
-        if swap_status == "swap.created" {
-            println!("Your turn: Pay the invoice");
-        }
-        if swap_status == "transaction.mempool" || swap_status == "transaction.confirmed" {
-            println!("*******BOLTZ******************");
-            println!("*******ONCHAIN-TX*************");
-            println!("*******DETECTED***************");
-            let script_balance = rev_script.get_balance(network_config.clone()).unwrap();
-            println!(
-                "confirmed: {}, unconfirmed: {}",
-                script_balance.0, script_balance.1
-            );
-            return Ok(true);
+/// Replaces our unconfirmed transaction `txid` with one paying `fee_rate`.
+fn bump_fee(
+    wallet: &Wallet<bdk::database::SqliteDatabase>,
+    txid: &Txid,
+    fee_rate: FeeRate,
+    frozen: &[OutPoint],
+    blockchain: &ElectrumBlockchain,
+    yes: bool,
+) -> Result<(Transaction, Option<u64>), String> {
+    wallet
+        .sync(blockchain, SyncOptions::default())
+        .map_err(|e| e.to_string())?;
+    let original = wallet
+        .get_tx(txid, false)
+        .map_err(|e| e.to_string())?
+        .ok_or(format!("Transaction {} is not in this wallet.", txid))?;
+    if original.sent == 0 {
+        return Err(format!("Transaction {} does not spend our coins.", txid));
+    }
+    if original.confirmation_time.is_some() {
+        return Err(format!("Transaction {} is already confirmed.", txid));
+    }
+
+    let mut tx_builder = wallet.build_fee_bump(*txid).map_err(|e| e.to_string())?;
+    tx_builder
+        .fee_rate(fee_rate)
+        .enable_rbf()
+        .unspendable(frozen.to_vec());
+    let (mut psbt, details) = tx_builder.finish().map_err(|e| e.to_string())?;
+    if let Some(fee) = original.fee {
+        info(&format!("Previous fee: {} sats", fee));
+    }
+    confirm_fee(details.fee, fee_rate, yes)?;
+
+    if !wallet
+        .sign(&mut psbt, SignOptions::default())
+        .map_err(|e| e.to_string())?
+    {
+        return Err("Could not sign the replacement.".to_string());
+    }
+    let tx = psbt.extract_tx();
+    blockchain.broadcast(&tx).map_err(|e| e.to_string())?;
+    Ok((tx, details.fee))
+}
+
+/// Points a swap whose lockup was `replaced` by a fee bump at the `replacement`.
+fn replace_lockup_txid(replaced: &Txid, replacement: &Txid) -> Result<(), String> {
+    let db_path = get_db_path()?;
+    for mut swap in read_swaps(&db_path)? {
+        if swap.lockup_txid == Some(replaced.to_string()) {
+            swap.lockup_txid = Some(replacement.to_string());
+            save_swap(&db_path, &swap)?;
         }
     }
+    Ok(())
 }
 
-fn build_and_claim_tx() -> () {}
+/// Spends our largest unfrozen output of the unconfirmed transaction `txid` back to us, paying
+/// enough that parent and child together reach `fee_rate`.
+fn cpfp(
+    wallet: &Wallet<bdk::database::SqliteDatabase>,
+    txid: &Txid,
+    fee_rate: FeeRate,
+    frozen: &[OutPoint],
+    blockchain: &ElectrumBlockchain,
+    yes: bool,
+) -> Result<(Transaction, Option<u64>), String> {
+    wallet
+        .sync(blockchain, SyncOptions::default())
+        .map_err(|e| e.to_string())?;
+    let parent = wallet
+        .get_tx(txid, true)
+        .map_err(|e| e.to_string())?
+        .ok_or(format!("Transaction {} is not in this wallet.", txid))?;
+    if parent.confirmation_time.is_some() {
+        return Err(format!("Transaction {} is already confirmed.", txid));
+    }
+    let parent_tx = parent.transaction.ok_or(format!(
+        "Transaction {} is missing from the wallet database.",
+        txid
+    ))?;
+    // incoming transactions spend coins bdk does not know
+    let parent_fee = match parent.fee {
+        Some(fee) => fee,
+        None => transaction_fee(&parent_tx, blockchain)?,
+    };
+    let parent_vsize = parent_tx.vsize();
+    if parent_fee as f32 >= fee_rate.as_sat_per_vb() * parent_vsize as f32 {
+        return Err(format!(
+            "Transaction {} already pays {:.1} sat/vB.",
+            txid,
+            parent_fee as f32 / parent_vsize as f32
+        ));
+    }
+    let outputs: Vec<LocalUtxo> = wallet
+        .list_unspent()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|utxo| utxo.outpoint.txid == *txid)
+        .collect();
+    if outputs.is_empty() {
+        return Err(format!(
+            "No unspent output of {} belongs to this wallet.",
+            txid
+        ));
+    }
+    let utxo = outputs
+        .into_iter()
+        .filter(|utxo| !frozen.contains(&utxo.outpoint))
+        .max_by_key(|utxo| utxo.txout.value)
+        .ok_or(format!(
+            "Every output of {} is frozen. Unfreeze one with swappy utxos unfreeze first.",
+            txid
+        ))?;
+    let drain_script = wallet
+        .get_address(AddressIndex::New)
+        .map_err(|e| e.to_string())?
+        .script_pubkey();
+
+    let build_child = |fee: u64| -> Result<PartiallySignedTransaction, String> {
+        let mut tx_builder = wallet.build_tx();
+        tx_builder
+            .add_utxo(utxo.outpoint)
+            .map_err(|e| e.to_string())?
+            .manually_selected_only()
+            .drain_to(drain_script.clone())
+            .fee_absolute(fee)
+            .enable_rbf();
+        let (mut psbt, _) = tx_builder.finish().map_err(|e| e.to_string())?;
+        if !wallet
+            .sign(&mut psbt, SignOptions::default())
+            .map_err(|e| e.to_string())?
+        {
+            return Err("Could not sign the child transaction.".to_string());
+        }
+        Ok(psbt)
+    };
+    // the child's size does not depend on its fee, so a first build measures it
+    let child_vsize = build_child(0)?.extract_tx().vsize();
+    let child_fee = cpfp_child_fee(parent_fee, parent_vsize, child_vsize, fee_rate);
+    info(&format!(
+        "Parent pays {} sats for {} vB, the child adds {} sats for {} vB",
+        parent_fee, parent_vsize, child_fee, child_vsize
+    ));
+    confirm_fee(Some(parent_fee + child_fee), fee_rate, yes)?;
 
-// then create script and tx
-// drain funds into local wallet
+    let tx = build_child(child_fee)?.extract_tx();
+    blockchain.broadcast(&tx).map_err(|e| e.to_string())?;
+    Ok((tx, Some(child_fee)))
+}
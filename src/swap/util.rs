@@ -0,0 +1,642 @@
+use crate::db::{
+    next_swap_key_index, read_swaps, save_swap, NetworkInfoModel, SwapKind, SwapModel, SwapState,
+};
+use bdk::bitcoin::Address;
+use bdk::blockchain::GetHeight;
+use boltz_client::swaps::bitcoin::{BtcSwapScript, BtcSwapTx};
+use boltz_client::swaps::boltz::{BoltzApiClient, CreateSwapRequest, SwapStatusRequest, SwapType};
+use boltz_client::util::derivation::SwapKey;
+use boltz_client::util::preimage::Preimage;
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The boltz api configured for this wallet. Bare hosts are assumed to be https.
+fn boltz_url(wallet_info: &NetworkInfoModel) -> String {
+    if wallet_info.boltz_url.contains("://") {
+        wallet_info.boltz_url.clone()
+    } else {
+        format!("https://{}", wallet_info.boltz_url)
+    }
+}
+
+/// What we pay for a claim or refund tx.
+const SWAP_TX_FEE: u64 = 300;
+
+/// Limits and fees of the boltz BTC/BTC pair, in sats and percent.
+#[derive(Debug, Clone)]
+pub struct PairInfo {
+    pub hash: String,
+    pub minimal: u64,
+    pub maximal: u64,
+    pub percentage: f64,
+    pub percentage_swap_in: f64,
+    pub submarine_miner_fee: u64,
+    pub reverse_lockup_fee: u64,
+}
+
+/// What a swap costs, shown to the user before anything is created.
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapQuote {
+    pub kind: SwapKind,
+    /// onchain lockup (submarine) or invoice amount (reverse)
+    pub amount_sent: u64,
+    /// invoice amount (submarine) or onchain amount claimed (reverse)
+    pub amount_received: u64,
+    pub boltz_fee: u64,
+    pub miner_fees: u64,
+    pub total_cost: u64,
+}
+
+impl PairInfo {
+    /// amount is the invoice amount in sats for both swap kinds.
+    pub fn quote(&self, kind: SwapKind, amount: u64) -> Result<SwapQuote, String> {
+        if amount < self.minimal || amount > self.maximal {
+            return Err(format!(
+                "Amount {} sats is outside boltz limits ({} - {} sats)",
+                amount, self.minimal, self.maximal
+            ));
+        }
+        let (amount_sent, amount_received, boltz_fee, miner_fees) = match kind {
+            SwapKind::Submarine => {
+                let boltz_fee = (amount as f64 * self.percentage_swap_in / 100.0).ceil() as u64;
+                let miner_fees = self.submarine_miner_fee;
+                (
+                    amount + boltz_fee + miner_fees,
+                    amount,
+                    boltz_fee,
+                    miner_fees,
+                )
+            }
+            SwapKind::Reverse => {
+                let boltz_fee = (amount as f64 * self.percentage / 100.0).ceil() as u64;
+                let miner_fees = self.reverse_lockup_fee + SWAP_TX_FEE;
+                let deductions = boltz_fee + miner_fees;
+                if deductions >= amount {
+                    return Err(format!(
+                        "Amount {} sats does not cover swap fees of {} sats",
+                        amount, deductions
+                    ));
+                }
+                (amount, amount - deductions, boltz_fee, miner_fees)
+            }
+        };
+        Ok(SwapQuote {
+            kind,
+            amount_sent,
+            amount_received,
+            boltz_fee,
+            miner_fees,
+            total_cost: amount_sent - amount_received,
+        })
+    }
+}
+
+pub fn get_pair_info(wallet_info: &NetworkInfoModel) -> Result<PairInfo, String> {
+    let boltz_client = BoltzApiClient::new(&boltz_url(wallet_info));
+    let boltz_pairs = boltz_client.get_pairs().map_err(|e| format!("{:?}", e))?;
+    let pair = match boltz_pairs.pairs.pairs.get("BTC/BTC") {
+        Some(pair) => pair,
+        None => return Err("Boltz does not offer the BTC/BTC pair".to_string()),
+    };
+    let miner_fees = &pair.fees.miner_fees.base_asset;
+    Ok(PairInfo {
+        hash: pair.hash.clone(),
+        minimal: pair.limits.minimal as u64,
+        maximal: pair.limits.maximal as u64,
+        percentage: pair.fees.percentage as f64,
+        percentage_swap_in: pair.fees.percentage_swap_in as f64,
+        submarine_miner_fee: miner_fees.normal as u64,
+        reverse_lockup_fee: miner_fees.reverse.lockup as u64,
+    })
+}
+
+pub fn create_submarine_swap(
+    invoice_str: &str,
+    refund_address: &str,
+    pair: &PairInfo,
+    network_info: &NetworkInfoModel,
+    db_path: &Path,
+) -> Result<SwapModel, String> {
+    // ensure the payment hash is the one boltz uses in their swap script
+    let mnemonic = network_info.display_secret();
+    let key_index = next_swap_key_index(db_path)?;
+
+    let keypair = SwapKey::from_submarine_account(
+        &mnemonic.to_string(),
+        &network_info.bip39_passphrase(),
+        network_info.chain(),
+        key_index,
+    )
+    .map_err(|e| format!("{:?}", e))?
+    .keypair;
+
+    // fees and limits were quoted and confirmed from this pair
+    let boltz_client = BoltzApiClient::new(&boltz_url(network_info));
+    let pair_hash = pair.hash.clone();
+
+    let request = CreateSwapRequest::new_btc_submarine(
+        pair_hash,
+        invoice_str.to_string(),
+        keypair.public_key().to_string().clone(),
+    );
+    let response = boltz_client
+        .create_swap(request)
+        .map_err(|e| format!("{:?}", e))?;
+    let preimage_states =
+        Preimage::from_invoice_str(invoice_str).map_err(|e| format!("{:?}", e))?;
+
+    if !response.validate_script_preimage160(preimage_states.clone().hash160) {
+        return Err(format!(
+            "Boltz swap {} does not lock to the invoice's payment hash",
+            response.id
+        ));
+    }
+
+    let timeout = response
+        .timeout_block_height
+        .ok_or("Boltz did not send a timeout block height")?;
+    let id = response.id.as_str();
+    let funding_address = response
+        .address
+        .clone()
+        .ok_or("Boltz did not send a lockup address")?;
+    let redeem_script_string = response
+        .redeem_script
+        .clone()
+        .ok_or("Boltz did not send a redeem script")?;
+    //funding_amount is u64. shouldn't it be f64 to repressent 0.00001 BTC?
+    let funding_amount = response
+        .expected_amount
+        .ok_or("Boltz did not send the expected amount")?;
+
+    let boltz_script =
+        BtcSwapScript::submarine_from_str(&redeem_script_string).map_err(|e| format!("{:?}", e))?;
+
+    let constructed_script = BtcSwapScript::new(
+        SwapType::Submarine,
+        preimage_states.hash160.to_string(),
+        boltz_script.reciever_pubkey.clone(),
+        timeout as u32,
+        keypair.public_key().to_string().clone(),
+    );
+
+    if boltz_script != constructed_script {
+        return Err(format!(
+            "Boltz redeem script for swap {} does not match our keys",
+            id
+        ));
+    }
+
+    let swap = SwapModel {
+        id: id.to_string(),
+        kind: SwapKind::Submarine,
+        state: SwapState::Created,
+        key_index,
+        redeem_script: redeem_script_string,
+        preimage: None,
+        invoice: invoice_str.to_string(),
+        lockup_address: funding_address,
+        amount: funding_amount,
+        timeout_block_height: timeout as u64,
+        output_address: refund_address.to_string(),
+        boltz_status: None,
+        lockup_txid: None,
+        spend_txid: None,
+    };
+    // persist before funding, otherwise a crash after funding leaves no way to refund
+    save_swap(db_path, &swap)?;
+
+    Ok(swap)
+}
+
+/// Creates a reverse swap for the confirmed `quote`, rejecting it if boltz would lock less than quoted.
+pub fn create_reverse_submarine_swap(
+    out_amount: &u64,
+    claim_address: &str,
+    pair: &PairInfo,
+    quote: &SwapQuote,
+    wallet_info: &NetworkInfoModel,
+    db_path: &Path,
+) -> Result<SwapModel, String> {
+    // returns invoice to get paid in receive
+
+    let mnemonic = wallet_info.display_secret();
+    let key_index = next_swap_key_index(db_path)?;
+
+    let keypair = SwapKey::from_reverse_account(
+        &mnemonic.to_string(),
+        &wallet_info.bip39_passphrase(),
+        wallet_info.chain(),
+        key_index,
+    )
+    .map_err(|e| format!("{:?}", e))?
+    .keypair;
+    let preimage = Preimage::new();
+
+    let network_config = wallet_info.electrum_config();
+
+    // fees and limits were quoted and confirmed from this pair
+    let boltz_client = BoltzApiClient::new(&boltz_url(wallet_info));
+    let pair_hash = pair.hash.clone();
+
+    let request = CreateSwapRequest::new_btc_reverse(
+        pair_hash,
+        preimage.clone().sha256.to_string(),
+        keypair.public_key().to_string().clone(),
+        // timeout as u64,
+        out_amount.clone(),
+    );
+    let response = boltz_client
+        .create_swap(request)
+        .map_err(|e| format!("{:?}", e))?;
+    if !response.validate_invoice_preimage256(preimage.clone().sha256) {
+        return Err(format!(
+            "Boltz invoice for swap {} does not pay to our preimage",
+            response.id
+        ));
+    }
+
+    let timeout = response
+        .timeout_block_height
+        .ok_or("Boltz did not send a timeout block height")?;
+    let id = response.id.as_str();
+    // the quote already deducts our claim fee from what boltz locks
+    let onchain_amount = response
+        .onchain_amount
+        .ok_or("Boltz did not send the onchain amount")?;
+    if onchain_amount < quote.amount_received + SWAP_TX_FEE {
+        return Err(format!(
+            "Boltz would lock {} sats, less than the quoted {}. Not using swap {}.",
+            onchain_amount,
+            quote.amount_received + SWAP_TX_FEE,
+            id
+        ));
+    }
+    let invoice = response
+        .invoice
+        .clone()
+        .ok_or("Boltz did not send an invoice")?;
+    let lockup_address = response
+        .lockup_address
+        .clone()
+        .ok_or("Boltz did not send a lockup address")?;
+    let redeem_script_string = response
+        .redeem_script
+        .clone()
+        .ok_or("Boltz did not send a redeem script")?;
+
+    let boltz_rev_script =
+        BtcSwapScript::reverse_from_str(&redeem_script_string).map_err(|e| format!("{:?}", e))?;
+
+    let constructed_rev_script = BtcSwapScript::new(
+        SwapType::ReverseSubmarine,
+        preimage.hash160.to_string(),
+        keypair.public_key().to_string().clone(),
+        timeout as u32,
+        boltz_rev_script.sender_pubkey.clone(),
+    );
+
+    if constructed_rev_script != boltz_rev_script {
+        return Err(format!(
+            "Boltz redeem script for swap {} does not match our keys",
+            id
+        ));
+    }
+
+    let constructed_address = constructed_rev_script
+        .to_address(network_config.network())
+        .map_err(|e| format!("{:?}", e))?;
+    // compare scripts, not strings: regtest prefixes differ between boltz's chain and ours
+    let boltz_address = Address::from_str(&lockup_address).map_err(|e| e.to_string())?;
+    if constructed_address.script_pubkey().as_bytes() != boltz_address.script_pubkey().as_bytes() {
+        return Err(format!(
+            "Boltz lockup address {} does not match the swap script",
+            lockup_address
+        ));
+    }
+
+    let script_balance = constructed_rev_script
+        .get_balance(network_config.clone())
+        .map_err(|e| format!("{:?}", e))?;
+    if script_balance.0 != 0 || script_balance.1 != 0 {
+        return Err(format!(
+            "Lockup address {} of swap {} is already in use",
+            lockup_address, id
+        ));
+    }
+
+    let swap = SwapModel {
+        id: id.to_string(),
+        kind: SwapKind::Reverse,
+        state: SwapState::Created,
+        key_index,
+        redeem_script: redeem_script_string,
+        preimage: preimage.to_string(),
+        invoice,
+        lockup_address,
+        // the claim spends what boltz locks, not the invoice amount
+        amount: onchain_amount,
+        timeout_block_height: timeout as u64,
+        output_address: claim_address.to_string(),
+        boltz_status: None,
+        lockup_txid: None,
+        spend_txid: None,
+    };
+    // persist before handing out the invoice, the preimage is needed to claim once it is paid
+    save_swap(db_path, &swap)?;
+    Ok(swap)
+}
+
+pub fn fetch_swap_status(id: &str, wallet_info: &NetworkInfoModel) -> Result<String, String> {
+    let boltz_client = BoltzApiClient::new(&boltz_url(wallet_info));
+    let request = SwapStatusRequest { id: id.to_string() };
+    let response = boltz_client
+        .swap_status(request)
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(response.status)
+}
+
+/// Maps a boltz status update onto our local swap state. Local progress (claimed/refunded) is never undone.
+pub fn next_state(kind: SwapKind, current: SwapState, boltz_status: &str) -> SwapState {
+    if current.is_final() {
+        return current;
+    }
+    match kind {
+        SwapKind::Reverse => match boltz_status {
+            "transaction.mempool" | "transaction.confirmed" => SwapState::Claimable,
+            "invoice.settled" => SwapState::Settled,
+            "swap.expired" | "transaction.failed" | "transaction.refunded" => SwapState::Failed,
+            _ => current,
+        },
+        SwapKind::Submarine => match boltz_status {
+            "transaction.mempool" | "transaction.confirmed" | "invoice.pending" => {
+                SwapState::Funded
+            }
+            "invoice.paid" | "transaction.claimed" => SwapState::Settled,
+            // boltz saw our lockup but rejects it (wrong amount or too late), it is ours to refund
+            "transaction.lockupFailed" => SwapState::Refundable,
+            "invoice.failedToPay" | "swap.expired" => {
+                // callers check the lockup address before trusting this for a Created swap
+                if current == SwapState::Created {
+                    SwapState::Failed
+                } else {
+                    SwapState::Refundable
+                }
+            }
+            _ => current,
+        },
+    }
+}
+
+/// Moves a swap one step forward: polls boltz, claims or refunds if possible and persists every transition.
+pub fn advance_swap(
+    mut swap: SwapModel,
+    wallet_info: &NetworkInfoModel,
+    db_path: &Path,
+) -> Result<SwapModel, String> {
+    if swap.state.is_final() {
+        return Ok(swap);
+    }
+    let status = fetch_swap_status(&swap.id, wallet_info)?;
+    let mut state = next_state(swap.kind, swap.state, &status);
+    // a lockup boltz never reported (cosigned, or we crashed before saving Funded) still needs a refund
+    if state == SwapState::Failed
+        && swap.kind == SwapKind::Submarine
+        && lockup_has_funds(&swap, wallet_info)?
+    {
+        state = SwapState::Refundable;
+    }
+    if state != swap.state || swap.boltz_status.as_deref() != Some(status.as_str()) {
+        swap.state = state;
+        swap.boltz_status = Some(status);
+        save_swap(db_path, &swap)?;
+    }
+
+    if swap.state == SwapState::Claimable {
+        let txid = claim_reverse_swap(&swap, wallet_info)?;
+        swap.state = SwapState::Claimed;
+        swap.spend_txid = Some(txid);
+        save_swap(db_path, &swap)?;
+    }
+
+    if swap.state == SwapState::Funded || swap.state == SwapState::Refundable {
+        let height = get_tip_height(wallet_info)?;
+        if (height as u64) < swap.timeout_block_height {
+            if swap.state == SwapState::Refundable {
+                eprintln!(
+                    "Swap {} can be refunded after block {} (current {}).",
+                    swap.id, swap.timeout_block_height, height
+                );
+            }
+            return Ok(swap);
+        }
+        // past the timeout boltz has not claimed our lockup; take it back
+        swap = refund_swap(swap, wallet_info, db_path)?;
+    }
+    Ok(swap)
+}
+
+fn lockup_has_funds(swap: &SwapModel, wallet_info: &NetworkInfoModel) -> Result<bool, String> {
+    let script =
+        BtcSwapScript::submarine_from_str(&swap.redeem_script).map_err(|e| format!("{:?}", e))?;
+    let (confirmed, unconfirmed) = script
+        .get_balance(wallet_info.electrum_config())
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(confirmed > 0 || unconfirmed > 0)
+}
+
+/// Only funded submarine swaps have a lockup to refund.
+pub fn check_refundable(swap: &SwapModel) -> Result<(), String> {
+    if swap.kind != SwapKind::Submarine {
+        return Err(format!("Swap {} is not a submarine swap", swap.id));
+    }
+    if swap.state != SwapState::Funded && swap.state != SwapState::Refundable {
+        return Err(format!(
+            "Swap {} is {:?}, only funded swaps can be refunded",
+            swap.id, swap.state
+        ));
+    }
+    Ok(())
+}
+
+/// Refunds a funded submarine swap to its output address. Only valid once the timeout block height is reached.
+pub fn refund_swap(
+    mut swap: SwapModel,
+    wallet_info: &NetworkInfoModel,
+    db_path: &Path,
+) -> Result<SwapModel, String> {
+    check_refundable(&swap)?;
+    if swap.state != SwapState::Refundable {
+        swap.state = SwapState::Refundable;
+        save_swap(db_path, &swap)?;
+    }
+    let txid = refund_submarine_swap(&swap, wallet_info)?;
+    swap.state = SwapState::Refunded;
+    swap.spend_txid = Some(txid);
+    save_swap(db_path, &swap)?;
+    Ok(swap)
+}
+
+pub fn get_tip_height(wallet_info: &NetworkInfoModel) -> Result<u32, String> {
+    let blockchain = wallet_info.electrum_blockchain()?;
+    blockchain.get_height().map_err(|e| e.to_string())
+}
+
+fn claim_reverse_swap(swap: &SwapModel, wallet_info: &NetworkInfoModel) -> Result<String, String> {
+    let mnemonic = wallet_info.display_secret();
+    let keypair = SwapKey::from_reverse_account(
+        &mnemonic.to_string(),
+        &wallet_info.bip39_passphrase(),
+        wallet_info.chain(),
+        swap.key_index,
+    )
+    .map_err(|e| format!("{:?}", e))?
+    .keypair;
+    let preimage = match &swap.preimage {
+        Some(preimage) => Preimage::from_str(preimage).map_err(|e| format!("{:?}", e))?,
+        None => return Err(format!("Swap {} has no preimage to claim with", swap.id)),
+    };
+    let rev_script =
+        BtcSwapScript::reverse_from_str(&swap.redeem_script).map_err(|e| format!("{:?}", e))?;
+
+    let network_config = wallet_info.electrum_config();
+    // Create SwapTx
+    let absolute_fees = SWAP_TX_FEE;
+    let mut rv_claim_tx = BtcSwapTx::new_claim(
+        rev_script,
+        swap.output_address.clone(),
+        network_config.network(),
+    )
+    .map_err(|e| format!("{:?}", e))?;
+    rv_claim_tx
+        .fetch_utxo(swap.amount, network_config.clone())
+        .map_err(|e| format!("{:?}", e))?;
+    let signed_tx = rv_claim_tx
+        .drain(keypair, preimage, absolute_fees)
+        .map_err(|e| format!("{:?}", e))?;
+    rv_claim_tx
+        .broadcast(signed_tx, network_config)
+        .map_err(|e| format!("{:?}", e))
+}
+
+fn refund_submarine_swap(
+    swap: &SwapModel,
+    wallet_info: &NetworkInfoModel,
+) -> Result<String, String> {
+    let mnemonic = wallet_info.display_secret();
+    let keypair = SwapKey::from_submarine_account(
+        &mnemonic.to_string(),
+        &wallet_info.bip39_passphrase(),
+        wallet_info.chain(),
+        swap.key_index,
+    )
+    .map_err(|e| format!("{:?}", e))?
+    .keypair;
+    let preimage = Preimage::from_invoice_str(&swap.invoice).map_err(|e| format!("{:?}", e))?;
+    let script =
+        BtcSwapScript::submarine_from_str(&swap.redeem_script).map_err(|e| format!("{:?}", e))?;
+
+    let network_config = wallet_info.electrum_config();
+    let absolute_fees = SWAP_TX_FEE;
+    let mut refund_tx = BtcSwapTx::new_refund(
+        script,
+        swap.output_address.clone(),
+        network_config.network(),
+    )
+    .map_err(|e| format!("{:?}", e))?;
+    refund_tx
+        .fetch_utxo(swap.amount, network_config.clone())
+        .map_err(|e| format!("{:?}", e))?;
+    let signed_tx = refund_tx
+        .drain(keypair, preimage, absolute_fees)
+        .map_err(|e| format!("{:?}", e))?;
+    refund_tx
+        .broadcast(signed_tx, network_config)
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// Drives every unfinished swap in the db. Safe to run repeatedly; returns the ids that failed to advance.
+pub fn resume_swaps(wallet_info: &NetworkInfoModel, db_path: &Path) -> Result<Vec<String>, String> {
+    let mut failed = vec![];
+    for swap in read_swaps(db_path)? {
+        if swap.state.is_final() {
+            continue;
+        }
+        let id = swap.id.clone();
+        match advance_swap(swap, wallet_info, db_path) {
+            Ok(swap) => {
+                eprintln!("{} {:?}: {:?}", swap.id, swap.kind, swap.state);
+            }
+            Err(e) => {
+                eprintln!("Error resuming swap {}: {}", id, e);
+                failed.push(id);
+            }
+        }
+    }
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_state() {
+        let state = next_state(SwapKind::Reverse, SwapState::Created, "swap.created");
+        assert_eq!(state, SwapState::Created);
+        let state = next_state(SwapKind::Reverse, state, "transaction.mempool");
+        assert_eq!(state, SwapState::Claimable);
+        // our claim is never undone by a late boltz update
+        let state = next_state(SwapKind::Reverse, SwapState::Claimed, "swap.expired");
+        assert_eq!(state, SwapState::Claimed);
+
+        let state = next_state(SwapKind::Submarine, SwapState::Created, "swap.expired");
+        assert_eq!(state, SwapState::Failed);
+        let state = next_state(
+            SwapKind::Submarine,
+            SwapState::Created,
+            "transaction.lockupFailed",
+        );
+        assert_eq!(state, SwapState::Refundable);
+        let state = next_state(
+            SwapKind::Submarine,
+            SwapState::Created,
+            "transaction.mempool",
+        );
+        assert_eq!(state, SwapState::Funded);
+        let state = next_state(SwapKind::Submarine, state, "invoice.failedToPay");
+        assert_eq!(state, SwapState::Refundable);
+        let state = next_state(
+            SwapKind::Submarine,
+            SwapState::Funded,
+            "transaction.claimed",
+        );
+        assert_eq!(state, SwapState::Settled);
+    }
+
+    #[test]
+    fn test_quote() {
+        let pair = PairInfo {
+            hash: "hash".to_string(),
+            minimal: 10_000,
+            maximal: 1_000_000,
+            percentage: 0.5,
+            percentage_swap_in: 0.1,
+            submarine_miner_fee: 1_000,
+            reverse_lockup_fee: 500,
+        };
+        let quote = pair.quote(SwapKind::Submarine, 100_000).unwrap();
+        assert_eq!(quote.amount_sent, 101_100);
+        assert_eq!(quote.amount_received, 100_000);
+        assert_eq!(quote.total_cost, 1_100);
+
+        let quote = pair.quote(SwapKind::Reverse, 100_000).unwrap();
+        assert_eq!(quote.amount_sent, 100_000);
+        assert_eq!(quote.amount_received, 100_000 - 500 - 500 - SWAP_TX_FEE);
+        assert_eq!(quote.total_cost, 1_000 + SWAP_TX_FEE);
+
+        assert!(pair.quote(SwapKind::Reverse, 9_999).is_err());
+        assert!(pair.quote(SwapKind::Submarine, 1_000_001).is_err());
+    }
+}
@@ -1 +1,4 @@
-pub mod e;
\ No newline at end of file
+pub mod e;
+pub mod output;
+pub mod seed;
+pub mod wallets;
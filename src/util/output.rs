@@ -0,0 +1,286 @@
+//! Machine readable output for `swappy --json`.
+//!
+//! In json mode every command writes one JSON object per line to stdout, tagged by `type`:
+//!
+//! - `status`: `{"confirmed", "unconfirmed", "transactions": [{"txid", "sent", "received", "fee", "confirmation_height"}]}`
+//! - `address`: `{"address"}`
+//! - `quote`: `{"kind", "amount_sent", "amount_received", "boltz_fee", "miner_fees", "total_cost"}`
+//! - `invoice`: `{"swap_id", "invoice", "amount"}`
+//! - `swap`: `{"id", "kind", "state", "amount", "timeout_block_height", "lockup_address", "boltz_status", "lockup_txid", "spend_txid"}`
+//! - `swaps`: `{"swaps": [swap, ..]}`
+//! - `transaction`: `{"txid", "fee"}`
+//! - `psbt`: `{"psbt", "fee"}`, a base64 PSBT from a watch-only `send chain` or `psbt create|sign|combine|finalize`
+//! - `psbt_info`: `{"txid", "inputs": [{"outpoint", "value", "signatures", "finalized"}], "outputs": [{"address", "value"}], "fee"}`
+//! - `seed`: `{"mnemonic"}`, only from `create` and `backup show-seed`
+//! - `config`: `{"network", "electrum_url", "electrum_tls", "electrum_validate_domain", "electrum_timeout", "boltz_url", "script_type", "fee_floor", "fee_ceiling"}`
+//! - `wallets`: `{"wallets": [name, ..], "current"}`
+//! - `utxos`: `{"utxos": [{"outpoint", "amount", "address", "confirmations", "label", "frozen"}]}`
+//! - `message`: `{"message"}`
+//! - `error`: `{"error"}`, after which swappy exits with status 1
+//!
+//! Amounts are in sats. Prompts and diagnostics go to stderr.
+
+use crate::db::{NetworkInfoModel, SwapKind, SwapModel, SwapState};
+use crate::swap::util::SwapQuote;
+use crate::wallet::psbt::{input_finalized, input_signatures, input_value, psbt_fee};
+use bdk::bitcoin::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::{Address, Network};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Output {
+    Status {
+        confirmed: u64,
+        unconfirmed: u64,
+        transactions: Vec<TransactionInfo>,
+    },
+    Address {
+        address: String,
+    },
+    Quote(SwapQuote),
+    Invoice {
+        swap_id: String,
+        invoice: String,
+        amount: u64,
+    },
+    Swap(SwapInfo),
+    Swaps {
+        swaps: Vec<SwapInfo>,
+    },
+    Transaction {
+        txid: String,
+        fee: Option<u64>,
+    },
+    Psbt {
+        psbt: String,
+        fee: Option<u64>,
+    },
+    PsbtInfo(PsbtInfo),
+    Config(ConfigInfo),
+    Seed {
+        mnemonic: String,
+    },
+    Wallets {
+        wallets: Vec<String>,
+        current: String,
+    },
+    Utxos {
+        utxos: Vec<UtxoInfo>,
+    },
+    Message {
+        message: String,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionInfo {
+    pub txid: String,
+    pub sent: u64,
+    pub received: u64,
+    pub fee: Option<u64>,
+    pub confirmation_height: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UtxoInfo {
+    pub outpoint: String,
+    pub amount: u64,
+    pub address: Option<String>,
+    pub confirmations: u32,
+    pub label: Option<String>,
+    pub frozen: bool,
+}
+
+/// A swap without the redeem script and preimage.
+#[derive(Debug, Serialize)]
+pub struct SwapInfo {
+    pub id: String,
+    pub kind: SwapKind,
+    pub state: SwapState,
+    pub amount: u64,
+    pub timeout_block_height: u64,
+    pub lockup_address: String,
+    pub boltz_status: Option<String>,
+    pub lockup_txid: Option<String>,
+    pub spend_txid: Option<String>,
+}
+
+impl From<&SwapModel> for SwapInfo {
+    fn from(swap: &SwapModel) -> Self {
+        SwapInfo {
+            id: swap.id.clone(),
+            kind: swap.kind,
+            state: swap.state,
+            amount: swap.amount,
+            timeout_block_height: swap.timeout_block_height,
+            lockup_address: swap.lockup_address.clone(),
+            boltz_status: swap.boltz_status.clone(),
+            lockup_txid: swap.lockup_txid.clone(),
+            spend_txid: swap.spend_txid.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PsbtInfo {
+    pub txid: String,
+    pub inputs: Vec<PsbtInputInfo>,
+    pub outputs: Vec<PsbtOutputInfo>,
+    pub fee: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PsbtInputInfo {
+    pub outpoint: String,
+    pub value: Option<u64>,
+    pub signatures: usize,
+    pub finalized: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PsbtOutputInfo {
+    /// None for scripts without an address, e.g. OP_RETURN
+    pub address: Option<String>,
+    pub value: u64,
+}
+
+impl PsbtInfo {
+    pub fn new(psbt: &PartiallySignedTransaction, network: Network) -> Self {
+        let tx = &psbt.unsigned_tx;
+        PsbtInfo {
+            txid: tx.txid().to_string(),
+            inputs: psbt
+                .inputs
+                .iter()
+                .zip(&tx.input)
+                .enumerate()
+                .map(|(index, (input, txin))| PsbtInputInfo {
+                    outpoint: txin.previous_output.to_string(),
+                    value: input_value(psbt, index),
+                    signatures: input_signatures(input),
+                    finalized: input_finalized(input),
+                })
+                .collect(),
+            outputs: tx
+                .output
+                .iter()
+                .map(|output| PsbtOutputInfo {
+                    address: Address::from_script(&output.script_pubkey, network)
+                        .ok()
+                        .map(|address| address.to_string()),
+                    value: output.value,
+                })
+                .collect(),
+            fee: psbt_fee(psbt),
+        }
+    }
+}
+
+/// Wallet settings without the mnemonic.
+#[derive(Debug, Serialize)]
+pub struct ConfigInfo {
+    pub network: String,
+    pub electrum_url: String,
+    pub electrum_tls: bool,
+    pub electrum_validate_domain: bool,
+    pub electrum_timeout: u8,
+    pub boltz_url: String,
+    pub script_type: String,
+    pub fee_floor: f32,
+    pub fee_ceiling: f32,
+}
+
+impl From<&NetworkInfoModel> for ConfigInfo {
+    fn from(wallet_info: &NetworkInfoModel) -> Self {
+        ConfigInfo {
+            network: wallet_info.network.to_string(),
+            electrum_url: wallet_info.electrum_url.clone(),
+            electrum_tls: wallet_info.electrum_tls,
+            electrum_validate_domain: wallet_info.electrum_validate_domain,
+            electrum_timeout: wallet_info.electrum_timeout,
+            boltz_url: wallet_info.boltz_url.clone(),
+            script_type: wallet_info.script_type.to_string(),
+            fee_floor: wallet_info.fee_floor,
+            fee_ceiling: wallet_info.fee_ceiling,
+        }
+    }
+}
+
+/// Writes `output` as a single line of JSON to stdout.
+pub fn emit(output: Output) {
+    println!("{}", serde_json::to_string(&output).unwrap());
+}
+
+/// Prints `message` in text mode, or emits it as a `message` object in json mode.
+pub fn message(message: &str) {
+    if is_json() {
+        emit(Output::Message {
+            message: message.to_string(),
+        });
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Progress and prompts: stdout in text mode, stderr in json mode so stdout stays parseable.
+pub fn info(message: &str) {
+    if is_json() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Prints `error` to stderr in text mode. In json mode emits an `error` object and exits with status 1.
+pub fn error(error: &str) {
+    if is_json() {
+        emit(Output::Error {
+            error: error.to_string(),
+        });
+        std::process::exit(1);
+    }
+    eprintln!("{}", error);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_output_is_tagged() {
+        let output = Output::Address {
+            address: "bcrt1qtest".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"type":"address","address":"bcrt1qtest"}"#
+        );
+        let output = Output::Quote(SwapQuote {
+            kind: SwapKind::Reverse,
+            amount_sent: 50_000,
+            amount_received: 48_450,
+            boltz_fee: 250,
+            miner_fees: 1_300,
+            total_cost: 1_550,
+        });
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"type":"quote","kind":"reverse","amount_sent":50000,"amount_received":48450,"boltz_fee":250,"miner_fees":1300,"total_cost":1550}"#
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+/// Encrypts the mnemonic with a key derived from passphrase. Output is salt || nonce || ciphertext.
+pub fn encrypt_seed(mnemonic: &str, passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, mnemonic.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut encrypted = salt.to_vec();
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+pub fn decrypt_seed(encrypted: &[u8], passphrase: &str) -> Result<String, String> {
+    if encrypted.len() < SALT_LEN + NONCE_LEN {
+        return Err("Encrypted seed is corrupt.".to_string());
+    }
+    let (salt, rest) = encrypted.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let key = derive_key(passphrase, salt)?;
+    let mnemonic = ChaCha20Poly1305::new(&key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong passphrase.".to_string())?;
+    String::from_utf8(mnemonic).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seed_roundtrip() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let encrypted = encrypt_seed(mnemonic, "hunter2").unwrap();
+        assert!(!encrypted
+            .windows(5)
+            .any(|window| window == "rebel".as_bytes()));
+        assert_eq!(decrypt_seed(&encrypted, "hunter2").unwrap(), mnemonic);
+        assert!(decrypt_seed(&encrypted, "hunter3").is_err());
+    }
+}
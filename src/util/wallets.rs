@@ -0,0 +1,161 @@
+//! Named wallets, each with its own sled db (settings and swaps) and bdk sqlite file.
+//!
+//! The `default` wallet keeps the original `$HOME/.swappy` and `$HOME/bdk` paths. Every other
+//! wallet lives in `$HOME/.swappy-wallets/<name>/`. The wallet used when `--wallet` is not given
+//! is stored in `$HOME/.swappy-wallets/.current`.
+
+use bdk::bitcoin::hashes::{sha256, Hash};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const DEFAULT_WALLET: &str = "default";
+const SWAPPY_DIR: &str = ".swappy";
+const WALLETS_DIR: &str = ".swappy-wallets";
+
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+/// Uses `name` instead of the current wallet for the rest of this run.
+pub fn select(name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    SELECTED
+        .set(name.to_string())
+        .map_err(|_| "A wallet is already selected.".to_string())
+}
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid wallet name {:?}: use letters, digits, - and _.",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn home() -> Result<PathBuf, String> {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .map_err(|e| format!("Failed to get HOME path: {}", e))
+}
+
+fn wallets_dir() -> Result<PathBuf, String> {
+    Ok(home()?.join(WALLETS_DIR))
+}
+
+/// Wallet names cannot start with a dot, so the pointer never clashes with a wallet dir.
+fn current_file() -> Result<PathBuf, String> {
+    Ok(wallets_dir()?.join(".current"))
+}
+
+fn pointer() -> Result<Option<String>, String> {
+    match std::fs::read_to_string(current_file()?) {
+        Ok(name) if !name.trim().is_empty() => Ok(Some(name.trim().to_string())),
+        _ => Ok(None),
+    }
+}
+
+/// The `--wallet` name, else the current wallet pointer, else `default`.
+pub fn current() -> Result<String, String> {
+    if let Some(name) = SELECTED.get() {
+        return Ok(name.clone());
+    }
+    Ok(pointer()?.unwrap_or_else(|| DEFAULT_WALLET.to_string()))
+}
+
+pub fn set_current(name: &str) -> Result<(), String> {
+    std::fs::create_dir_all(wallets_dir()?).map_err(|e| e.to_string())?;
+    std::fs::write(current_file()?, name).map_err(|e| e.to_string())
+}
+
+pub fn db_path(name: &str) -> Result<PathBuf, String> {
+    if name == DEFAULT_WALLET {
+        return Ok(home()?.join(SWAPPY_DIR));
+    }
+    Ok(wallets_dir()?.join(name).join("swappy"))
+}
+
+pub fn sqlite_path(name: &str) -> Result<PathBuf, String> {
+    if name == DEFAULT_WALLET {
+        return Ok(home()?.join("bdk"));
+    }
+    Ok(wallets_dir()?.join(name).join("bdk"))
+}
+
+/// The bdk sqlite file of one BIP39 passphrase of a prompted passphrase wallet, so every
+/// passphrase keeps its own history. Named by a hash of the deposit descriptor.
+pub fn passphrase_sqlite_path(name: &str, deposit_descriptor: &str) -> Result<PathBuf, String> {
+    let hash = sha256::Hash::hash(deposit_descriptor.as_bytes()).to_string();
+    let mut path = sqlite_path(name)?.into_os_string();
+    path.push(format!("-{}", &hash[..16]));
+    Ok(path.into())
+}
+
+fn is_passphrase_sqlite(file_name: &str) -> bool {
+    file_name.strip_prefix("bdk-").map_or(false, |hash| {
+        hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+pub fn exists(name: &str) -> Result<bool, String> {
+    Ok(db_path(name)?.exists())
+}
+
+/// Names of all created wallets, sorted.
+pub fn list() -> Result<Vec<String>, String> {
+    let mut names = vec![];
+    if exists(DEFAULT_WALLET)? {
+        names.push(DEFAULT_WALLET.to_string());
+    }
+    if let Ok(entries) = std::fs::read_dir(wallets_dir()?) {
+        for entry in entries {
+            let name = entry.map_err(|e| e.to_string())?.file_name();
+            let name = name.to_string_lossy();
+            if name != DEFAULT_WALLET && validate_name(&name).is_ok() && exists(&name)? {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Removes the wallet's settings, swaps and bdk databases.
+pub fn delete(name: &str) -> Result<(), String> {
+    std::fs::remove_dir_all(db_path(name)?).map_err(|e| e.to_string())?;
+    let sqlite_path = sqlite_path(name)?;
+    if sqlite_path.exists() {
+        std::fs::remove_file(&sqlite_path).map_err(|e| e.to_string())?;
+    }
+    if let Some(Ok(entries)) = sqlite_path.parent().map(std::fs::read_dir) {
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            if is_passphrase_sqlite(&entry.file_name().to_string_lossy()) {
+                std::fs::remove_file(entry.path()).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if name != DEFAULT_WALLET {
+        let _ = std::fs::remove_dir(wallets_dir()?.join(name));
+    }
+    if pointer()?.as_deref() == Some(name) {
+        std::fs::remove_file(current_file()?).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("treasury-2_b").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../.ssh").is_err());
+        assert!(validate_name("cold storage").is_err());
+    }
+}
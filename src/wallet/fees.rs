@@ -0,0 +1,155 @@
+use bdk::bitcoin::Transaction;
+use bdk::blockchain::{Blockchain, GetTx};
+use bdk::FeeRate;
+use std::str::FromStr;
+
+/// sat/vB bounds for wallets that never set fee_floor or fee_ceiling.
+pub const DEFAULT_FEE_FLOOR: f32 = 1.0;
+pub const DEFAULT_FEE_CEILING: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Fast,
+    Normal,
+    Slow,
+}
+
+impl Priority {
+    /// Blocks within which the transaction should confirm.
+    pub fn target_blocks(&self) -> usize {
+        match self {
+            Priority::Fast => 1,
+            Priority::Normal => 6,
+            Priority::Slow => 24,
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fast" => Ok(Priority::Fast),
+            "normal" => Ok(Priority::Normal),
+            "slow" => Ok(Priority::Slow),
+            _ => Err(format!(
+                "Unknown priority {}, expected fast, normal or slow.",
+                s
+            )),
+        }
+    }
+}
+
+/// An explicit sat/vB rate, or a priority to estimate one for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeChoice {
+    Priority(Priority),
+    Rate(f32),
+}
+
+/// An explicit rate must lie within floor and ceiling.
+pub fn check_fee_rate(rate: f32, floor: f32, ceiling: f32) -> Result<f32, String> {
+    if floor > ceiling {
+        return Err(format!(
+            "fee_floor {} is above fee_ceiling {}.",
+            floor, ceiling
+        ));
+    }
+    if !(floor..=ceiling).contains(&rate) {
+        return Err(format!(
+            "Fee rate {} sat/vB is outside {}..{} sat/vB. Change the bounds with swappy config set fee_floor|fee_ceiling.",
+            rate, floor, ceiling
+        ));
+    }
+    Ok(rate)
+}
+
+/// Estimates are raised to the floor and capped at the ceiling. Backends without an estimate (e.g. regtest) get the floor.
+pub fn clamp_estimate(estimate: f32, floor: f32, ceiling: f32) -> f32 {
+    if !estimate.is_finite() || estimate < floor {
+        floor
+    } else if estimate > ceiling {
+        ceiling
+    } else {
+        estimate
+    }
+}
+
+pub fn resolve_fee_rate(
+    choice: FeeChoice,
+    blockchain: &impl Blockchain,
+    floor: f32,
+    ceiling: f32,
+) -> Result<FeeRate, String> {
+    let rate = match choice {
+        FeeChoice::Rate(rate) => check_fee_rate(rate, floor, ceiling)?,
+        FeeChoice::Priority(priority) => {
+            check_fee_rate(floor, floor, ceiling)?;
+            let estimate = blockchain
+                .estimate_fee(priority.target_blocks())
+                .map_err(|e| format!("Error estimating fee: {}", e))?;
+            clamp_estimate(estimate.as_sat_per_vb(), floor, ceiling)
+        }
+    };
+    Ok(FeeRate::from_sat_per_vb(rate))
+}
+
+/// Inputs minus outputs, fetching the spent outputs from the backend.
+pub fn transaction_fee(tx: &Transaction, blockchain: &impl GetTx) -> Result<u64, String> {
+    let mut input_value = 0;
+    for input in &tx.input {
+        let previous = input.previous_output;
+        let value = blockchain
+            .get_tx(&previous.txid)
+            .map_err(|e| e.to_string())?
+            .and_then(|prev_tx| prev_tx.output.get(previous.vout as usize).map(|o| o.value))
+            .ok_or(format!("Could not find the output spent by {}.", previous))?;
+        input_value += value;
+    }
+    let output_value: u64 = tx.output.iter().map(|o| o.value).sum();
+    input_value
+        .checked_sub(output_value)
+        .ok_or("Transaction spends more than its inputs.".to_string())
+}
+
+/// Fee a child of `child_vsize` must pay so that it and its parent together pay `target`.
+/// The child never pays less than 1 sat/vB so it relays on its own.
+pub fn cpfp_child_fee(
+    parent_fee: u64,
+    parent_vsize: usize,
+    child_vsize: usize,
+    target: FeeRate,
+) -> u64 {
+    let package_fee = (target.as_sat_per_vb() * (parent_vsize + child_vsize) as f32).ceil() as u64;
+    package_fee
+        .saturating_sub(parent_fee)
+        .max(child_vsize as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fee_bounds() {
+        assert_eq!(check_fee_rate(12.5, 1.0, 500.0), Ok(12.5));
+        assert!(check_fee_rate(0.5, 1.0, 500.0).is_err());
+        assert!(check_fee_rate(501.0, 1.0, 500.0).is_err());
+        assert!(check_fee_rate(5.0, 10.0, 2.0).is_err());
+
+        assert_eq!(clamp_estimate(-1.0, 1.0, 500.0), 1.0);
+        assert_eq!(clamp_estimate(f32::NAN, 2.0, 500.0), 2.0);
+        assert_eq!(clamp_estimate(37.2, 1.0, 500.0), 37.2);
+        assert_eq!(clamp_estimate(900.0, 1.0, 500.0), 500.0);
+    }
+
+    #[test]
+    fn test_cpfp_child_fee() {
+        // a 300 sat claim of 150 vB lifted to 10 sat/vB by a 110 vB child
+        let target = FeeRate::from_sat_per_vb(10.0);
+        assert_eq!(cpfp_child_fee(300, 150, 110, target), 2_300);
+        // the parent alone already pays enough
+        assert_eq!(cpfp_child_fee(5_000, 150, 110, target), 110);
+    }
+}
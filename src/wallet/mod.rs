@@ -1 +1,3 @@
+pub mod fees;
+pub mod psbt;
 pub mod util;
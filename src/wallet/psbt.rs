@@ -0,0 +1,105 @@
+use bdk::bitcoin::psbt::{Input, PartiallySignedTransaction};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Reads a base64 PSBT file.
+pub fn read_psbt(path: &Path) -> Result<PartiallySignedTransaction, String> {
+    let base64 = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    PartiallySignedTransaction::from_str(base64.trim())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn write_psbt(psbt: &PartiallySignedTransaction, path: &Path) -> Result<(), String> {
+    std::fs::write(path, format!("{}\n", psbt)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Merges signatures and metadata of PSBTs spending the same transaction.
+pub fn combine_psbts(
+    psbts: Vec<PartiallySignedTransaction>,
+) -> Result<PartiallySignedTransaction, String> {
+    let mut psbts = psbts.into_iter();
+    let mut combined = psbts.next().ok_or("No PSBTs to combine.")?;
+    for psbt in psbts {
+        combined.combine(psbt).map_err(|e| e.to_string())?;
+    }
+    Ok(combined)
+}
+
+/// Value of the output spent by input `index`, if the PSBT carries it.
+pub fn input_value(psbt: &PartiallySignedTransaction, index: usize) -> Option<u64> {
+    let input = psbt.inputs.get(index)?;
+    let vout = psbt.unsigned_tx.input.get(index)?.previous_output.vout as usize;
+    match (&input.witness_utxo, &input.non_witness_utxo) {
+        (Some(utxo), _) => Some(utxo.value),
+        (None, Some(tx)) => tx.output.get(vout).map(|output| output.value),
+        (None, None) => None,
+    }
+}
+
+/// Inputs minus outputs, if every input carries the output it spends.
+pub fn psbt_fee(psbt: &PartiallySignedTransaction) -> Option<u64> {
+    let mut input_value_sum = 0;
+    for index in 0..psbt.inputs.len() {
+        input_value_sum += input_value(psbt, index)?;
+    }
+    let output_value: u64 = psbt.unsigned_tx.output.iter().map(|o| o.value).sum();
+    input_value_sum.checked_sub(output_value)
+}
+
+pub fn input_signatures(input: &Input) -> usize {
+    input.partial_sigs.len() + input.tap_key_sig.is_some() as usize
+}
+
+pub fn input_finalized(input: &Input) -> bool {
+    input.final_script_sig.is_some() || input.final_script_witness.is_some()
+}
+
+/// Signatures added to the PSBT so far, across all inputs.
+pub fn signature_count(psbt: &PartiallySignedTransaction) -> usize {
+    psbt.inputs.iter().map(input_signatures).sum()
+}
+
+pub fn is_finalized(psbt: &PartiallySignedTransaction) -> bool {
+    psbt.inputs.iter().all(input_finalized)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bdk::bitcoin::{
+        OutPoint, PackedLockTime, Script, Sequence, Transaction, TxIn, TxOut, Witness,
+    };
+
+    #[test]
+    fn test_psbt_fee_and_roundtrip() {
+        let tx = Transaction {
+            version: 2,
+            lock_time: PackedLockTime(0),
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 9_000,
+                script_pubkey: Script::new(),
+            }],
+        };
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+        assert_eq!(psbt_fee(&psbt), None);
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: 10_000,
+            script_pubkey: Script::new(),
+        });
+        assert_eq!(psbt_fee(&psbt), Some(1_000));
+        assert!(!is_finalized(&psbt));
+
+        let path = std::env::temp_dir().join(format!("swappy-test-{}.psbt", std::process::id()));
+        write_psbt(&psbt, &path).unwrap();
+        let read = read_psbt(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, psbt);
+        assert_eq!(combine_psbts(vec![read, psbt.clone()]).unwrap(), psbt);
+    }
+}
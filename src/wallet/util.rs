@@ -14,7 +14,6 @@ use std::path::Path;
 use std::str::FromStr;
 
 type S5Mnemonic = String;
-type S5Xpub = String;
 
 pub fn create_mnemonic() -> S5Mnemonic {
     let mnemonic: GeneratedKey<Mnemonic, Segwitv0> =
@@ -22,8 +21,18 @@ pub fn create_mnemonic() -> S5Mnemonic {
     mnemonic.to_string()
 }
 
-pub fn create_xpub() -> S5Xpub {
-    return "xpub".to_string();
+/// Checks the word count and checksum of a mnemonic being restored and normalizes its spacing.
+pub fn validate_mnemonic(mnemonic: &str) -> Result<S5Mnemonic, String> {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    if words.len() != 12 && words.len() != 24 {
+        return Err(format!(
+            "Expected a 12 or 24 word mnemonic, got {} words.",
+            words.len()
+        ));
+    }
+    let mnemonic = Mnemonic::parse_in(Language::English, words.join(" ").to_lowercase())
+        .map_err(|e| e.to_string())?;
+    Ok(mnemonic.to_string())
 }
 
 #[derive(Debug)]
@@ -32,69 +41,346 @@ pub struct Descriptors {
     pub change: String,
 }
 
-impl Descriptors {
-    pub fn new_public(xpub: &S5Xpub) -> Result<Self, String> {
-        let secp = Secp256k1::new();
-        let mnemonic: Result<Mnemonic, _> = Mnemonic::parse_in(Language::English, xpub);
-        match mnemonic {
-            Ok(mnemonic) => {
-                let seed = mnemonic.to_seed("");
-                let xprv = ExtendedPrivKey::new_master(Network::Testnet, &seed).unwrap();
-                let fp: Fingerprint = xprv.fingerprint(&secp);
-                let derivation_path = DerivationPath::from_str("m/84'/1'/0'").unwrap();
-                let derived_xprv = xprv.derive_priv(&secp, &derivation_path).unwrap();
-                let xpub = ExtendedPubKey::from_priv(&secp, &derived_xprv);
-                let descriptor = format!(
-                    "wpkh([{}/{}]{})",
-                    fp,
-                    derivation_path.to_string().replace("m/", ""),
-                    xpub
-                );
-                Ok(Descriptors {
-                    deposit: descriptor.replace(')', "/0/*)"),
-                    change: descriptor.replace(')', "/1/*)"),
-                })
+/// BIP44 coin type: 0 for mainnet, 1 for every test network.
+fn coin_type(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    }
+}
+
+/// Output script of a single key wallet, chosen at creation. Each uses its own BIP derivation path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScriptType {
+    /// native segwit, BIP84
+    #[default]
+    Wpkh,
+    /// taproot, BIP86
+    Tr,
+    /// nested segwit, BIP49
+    ShWpkh,
+    /// legacy, BIP44
+    Pkh,
+}
+
+impl ScriptType {
+    fn account_path(&self, network: Network) -> String {
+        let purpose = match self {
+            ScriptType::Wpkh => 84,
+            ScriptType::Tr => 86,
+            ScriptType::ShWpkh => 49,
+            ScriptType::Pkh => 44,
+        };
+        format!("m/{}'/{}'/0'", purpose, coin_type(network))
+    }
+
+    /// Wraps a key expression like `[fp/path]xpub/0/*` in a descriptor of this type.
+    fn descriptor(&self, key: &str) -> String {
+        match self {
+            ScriptType::Wpkh => format!("wpkh({})", key),
+            ScriptType::Tr => format!("tr({})", key),
+            ScriptType::ShWpkh => format!("sh(wpkh({}))", key),
+            ScriptType::Pkh => format!("pkh({})", key),
+        }
+    }
+
+    /// The single-key script type a descriptor uses, `None` for other policies like multisig.
+    pub fn of_descriptor(descriptor: &str) -> Option<Self> {
+        [
+            ScriptType::Wpkh,
+            ScriptType::Tr,
+            ScriptType::ShWpkh,
+            ScriptType::Pkh,
+        ]
+        .into_iter()
+        .find(|script_type| {
+            descriptor.starts_with(script_type.descriptor("").trim_end_matches(')'))
+        })
+    }
+}
+
+impl FromStr for ScriptType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wpkh" => Ok(ScriptType::Wpkh),
+            "tr" => Ok(ScriptType::Tr),
+            "sh-wpkh" => Ok(ScriptType::ShWpkh),
+            "pkh" => Ok(ScriptType::Pkh),
+            _ => Err(format!(
+                "Unknown script type {}, expected wpkh, tr, sh-wpkh or pkh.",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            ScriptType::Wpkh => "wpkh",
+            ScriptType::Tr => "tr",
+            ScriptType::ShWpkh => "sh-wpkh",
+            ScriptType::Pkh => "pkh",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// SLIP-132 version bytes, mapped to the plain xpub/tpub version and the script type they imply.
+const SLIP132_VERSIONS: [([u8; 4], [u8; 4], ScriptType); 4] = [
+    // zpub, vpub
+    (
+        [0x04, 0xb2, 0x47, 0x46],
+        [0x04, 0x88, 0xb2, 0x1e],
+        ScriptType::Wpkh,
+    ),
+    (
+        [0x04, 0x5f, 0x1c, 0xf6],
+        [0x04, 0x35, 0x87, 0xcf],
+        ScriptType::Wpkh,
+    ),
+    // ypub, upub
+    (
+        [0x04, 0x9d, 0x7c, 0xb2],
+        [0x04, 0x88, 0xb2, 0x1e],
+        ScriptType::ShWpkh,
+    ),
+    (
+        [0x04, 0x4a, 0x52, 0x62],
+        [0x04, 0x35, 0x87, 0xcf],
+        ScriptType::ShWpkh,
+    ),
+];
+
+/// Derives the account key at `path` and its origin `[fingerprint/path]`.
+fn derive_account(
+    mnemonic_str: &str,
+    passphrase: &str,
+    network: Network,
+    path: &str,
+) -> Result<(String, ExtendedPrivKey), String> {
+    let secp = Secp256k1::new();
+    let mnemonic =
+        Mnemonic::parse_in(Language::English, mnemonic_str).map_err(|e| e.to_string())?;
+    let seed = mnemonic.to_seed(passphrase);
+    let xprv = ExtendedPrivKey::new_master(network, &seed).map_err(|e| e.to_string())?;
+    let fp: Fingerprint = xprv.fingerprint(&secp);
+    let derivation_path = DerivationPath::from_str(path).map_err(|e| e.to_string())?;
+    let derived_xprv = xprv
+        .derive_priv(&secp, &derivation_path)
+        .map_err(|e| e.to_string())?;
+    let origin = format!("[{}/{}]", fp, derivation_path.to_string().replace("m/", ""));
+    Ok((origin, derived_xprv))
+}
+
+/// Splits `[origin]xpub` and decodes the key, converting SLIP-132 versions to a plain xpub/tpub.
+/// Returns the key with its origin and the script type a SLIP-132 version implies.
+fn parse_xpub(xpub: &str, network: Network) -> Result<(String, Option<ScriptType>), String> {
+    let (origin, key) = match xpub.trim().split_once(']') {
+        Some((origin, key)) => (format!("{}]", origin), key),
+        None => (String::new(), xpub.trim()),
+    };
+    let mut data = bitcoin::util::base58::from_check(key).map_err(|e| e.to_string())?;
+    let mut implied = None;
+    if data.len() >= 4 {
+        for (slip132, plain, slip132_type) in SLIP132_VERSIONS {
+            if data[..4] == slip132 {
+                data[..4].copy_from_slice(&plain);
+                implied = Some(slip132_type);
             }
-            Err(e) => Err(e.to_string()),
         }
     }
-    pub fn new_secret(mnemonic_str: &S5Mnemonic) -> Result<Self, String> {
-        let secp = Secp256k1::new();
-        let mnemonic: Result<Mnemonic, _> = Mnemonic::parse_in(Language::English, mnemonic_str);
-        match mnemonic {
-            Ok(mnemonic) => {
-                let seed = mnemonic.to_seed("");
-                let xprv = ExtendedPrivKey::new_master(Network::Testnet, &seed).unwrap();
-                let fp: Fingerprint = xprv.fingerprint(&secp);
-                let derivation_path = DerivationPath::from_str("m/84'/1'/0'").unwrap();
-                let derived_xprv = xprv.derive_priv(&secp, &derivation_path).unwrap();
-                let descriptor = format!(
-                    "wpkh([{}/{}]{})",
-                    fp,
-                    derivation_path.to_string().replace("m/", ""),
-                    derived_xprv
-                );
-                Ok(Descriptors {
-                    deposit: descriptor.replace(')', "/0/*)"),
-                    change: descriptor.replace(')', "/1/*)"),
-                })
+    let xpub = ExtendedPubKey::decode(&data).map_err(|e| e.to_string())?;
+    if (xpub.network == Network::Bitcoin) != (network == Network::Bitcoin) {
+        return Err(format!("Key is not for {}.", network));
+    }
+    Ok((format!("{}{}", origin, xpub), implied))
+}
+
+/// BIP48 path of native segwit multisig keys.
+fn multisig_path(network: Network) -> String {
+    format!("m/48'/{}'/0'/2'", coin_type(network))
+}
+
+/// This seed's multisig account key with origin, to share with cosigners.
+pub fn multisig_xpub(
+    mnemonic_str: &str,
+    passphrase: &str,
+    network: Network,
+) -> Result<String, String> {
+    let path = multisig_path(network);
+    let (origin, derived_xprv) = derive_account(mnemonic_str, passphrase, network, &path)?;
+    let xpub = ExtendedPubKey::from_priv(&Secp256k1::new(), &derived_xprv);
+    Ok(format!("{}{}", origin, xpub))
+}
+
+/// An m-of-n `wsh(sortedmulti)` policy between the local seed and the cosigners.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multisig {
+    pub threshold: usize,
+    /// account keys of the other signers, `[fingerprint/48'/coin'/0'/2']xpub`
+    pub cosigners: Vec<String>,
+}
+
+impl Multisig {
+    pub fn new(threshold: usize, cosigners: &[String], network: Network) -> Result<Self, String> {
+        if threshold == 0 || threshold > cosigners.len() + 1 {
+            return Err(format!(
+                "Threshold must be between 1 and {} signers.",
+                cosigners.len() + 1
+            ));
+        }
+        let cosigners = cosigners
+            .iter()
+            .map(|cosigner| {
+                parse_xpub(cosigner, network)
+                    .map(|(key, _)| key)
+                    .map_err(|e| format!("Invalid cosigner key {}: {}", cosigner, e))
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        if (1..cosigners.len()).any(|i| cosigners[i..].contains(&cosigners[i - 1])) {
+            return Err("The same cosigner key is listed twice.".to_string());
+        }
+        Ok(Multisig {
+            threshold,
+            cosigners,
+        })
+    }
+}
+
+impl Descriptors {
+    /// Receive (`/0/*`) and change (`/1/*`) descriptors for an account key with origin.
+    fn from_account_key(key: &str, script_type: ScriptType) -> Self {
+        Descriptors {
+            deposit: script_type.descriptor(&format!("{}/0/*", key)),
+            change: script_type.descriptor(&format!("{}/1/*", key)),
+        }
+    }
+    pub fn new_public(
+        mnemonic_str: &str,
+        passphrase: &str,
+        network: Network,
+        script_type: ScriptType,
+    ) -> Result<Self, String> {
+        let path = script_type.account_path(network);
+        let (origin, derived_xprv) = derive_account(mnemonic_str, passphrase, network, &path)?;
+        let xpub = ExtendedPubKey::from_priv(&Secp256k1::new(), &derived_xprv);
+        Ok(Descriptors::from_account_key(
+            &format!("{}{}", origin, xpub),
+            script_type,
+        ))
+    }
+    pub fn new_secret(
+        mnemonic_str: &str,
+        passphrase: &str,
+        network: Network,
+        script_type: ScriptType,
+    ) -> Result<Self, String> {
+        let path = script_type.account_path(network);
+        let (origin, derived_xprv) = derive_account(mnemonic_str, passphrase, network, &path)?;
+        Ok(Descriptors::from_account_key(
+            &format!("{}{}", origin, derived_xprv),
+            script_type,
+        ))
+    }
+
+    /// Watch-only descriptors for an account xpub/tpub, or a zpub/vpub/ypub/upub which implies the script type.
+    /// Plain xpubs default to wpkh. The key may be prefixed with its origin, e.g. `[7b51f3f7/84'/0'/0']xpub...`.
+    pub fn from_xpub(
+        xpub: &str,
+        network: Network,
+        script_type: Option<ScriptType>,
+    ) -> Result<Self, String> {
+        let (key, implied) = parse_xpub(xpub, network)?;
+        let script_type = match (script_type, implied) {
+            (Some(chosen), Some(implied)) if chosen != implied => {
+                return Err(format!("Key prefix is for {}, not {}.", implied, chosen));
             }
-            Err(e) => Err(e.to_string()),
+            (chosen, implied) => chosen.or(implied).unwrap_or_default(),
+        };
+        Ok(Descriptors::from_account_key(&key, script_type))
+    }
+
+    /// Public descriptors of a multisig wallet, with the local seed as one of the keys.
+    pub fn new_public_multisig(
+        mnemonic_str: &str,
+        passphrase: &str,
+        network: Network,
+        multisig: &Multisig,
+    ) -> Result<Self, String> {
+        let local = multisig_xpub(mnemonic_str, passphrase, network)?;
+        if multisig.cosigners.contains(&local) {
+            return Err("This wallet's own key is listed as a cosigner.".to_string());
+        }
+        let descriptors = Descriptors::from_multisig(&local, multisig);
+        Descriptor::<DescriptorPublicKey>::from_str(&descriptors.deposit)
+            .map_err(|e| format!("Invalid multisig policy: {}", e))?;
+        Ok(descriptors)
+    }
+    pub fn new_secret_multisig(
+        mnemonic_str: &str,
+        passphrase: &str,
+        network: Network,
+        multisig: &Multisig,
+    ) -> Result<Self, String> {
+        let path = multisig_path(network);
+        let (origin, derived_xprv) = derive_account(mnemonic_str, passphrase, network, &path)?;
+        Ok(Descriptors::from_multisig(
+            &format!("{}{}", origin, derived_xprv),
+            multisig,
+        ))
+    }
+    fn from_multisig(local: &str, multisig: &Multisig) -> Self {
+        let descriptor = |branch: u32| {
+            let keys: Vec<String> = std::iter::once(local)
+                .chain(multisig.cosigners.iter().map(String::as_str))
+                .map(|key| format!("{}/{}/*", key, branch))
+                .collect();
+            format!(
+                "wsh(sortedmulti({},{}))",
+                multisig.threshold,
+                keys.join(",")
+            )
+        };
+        Descriptors {
+            deposit: descriptor(0),
+            change: descriptor(1),
         }
     }
+
+    /// Watch-only descriptors from a public receive descriptor ending in `/0/*`; change uses `/1/*`.
+    pub fn from_descriptor(descriptor: &str) -> Result<Self, String> {
+        let descriptor = descriptor.trim();
+        let descriptor = match descriptor.split_once('#') {
+            Some((descriptor, _checksum)) => descriptor,
+            None => descriptor,
+        };
+        Descriptor::<DescriptorPublicKey>::from_str(descriptor)
+            .map_err(|e| format!("Invalid public descriptor: {}", e))?;
+        if !descriptor.contains("/0/*") {
+            return Err("Descriptor must derive receive addresses with /0/*.".to_string());
+        }
+        Ok(Descriptors {
+            deposit: descriptor.to_string(),
+            change: descriptor.replace("/0/*", "/1/*"),
+        })
+    }
 }
 
 pub fn create_wallet(
     descriptors: Descriptors,
+    network: Network,
     sqlite_path: &Path,
 ) -> Result<Wallet<bdk::database::SqliteDatabase>, String> {
     let wallet = Wallet::new(
         &descriptors.deposit,
         Some(&descriptors.change),
-        bitcoin::Network::Testnet,
+        network,
         SqliteDatabase::new(sqlite_path),
     )
-    .unwrap();
+    .map_err(|e| e.to_string())?;
     Ok(wallet)
 }
 
@@ -108,15 +394,167 @@ mod test {
         let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
         let expected_xpub  = "[7b51f3f7/84'/1'/0']tpubDCCnk1bwtxqNaFbQstA7iGuzKkooWrZZ6HxHeEQ3dZbKCDftjW7pLGMjdwh1mKXK52SW6TYyoGjzFWaaSAVLCs7aq2Y4TZyaWgocm9GxuoQ";
         let expected_deposit_descriptor = format!("wpkh({}/0/*)", expected_xpub);
-        let descriptors = Descriptors::new_public(mnemonic).unwrap();
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::Wpkh).unwrap();
         print!("{:#?}", descriptors);
         assert_eq!(descriptors.deposit, expected_deposit_descriptor);
     }
 
+    #[test]
+    fn test_create_mainnet_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[7b51f3f7/84'/0'/0']xpub6CN81kRTsmGFU4YFu6FFMqWMp1EsHWUby3moZpt1vuGKLQCRaqEV1gQwzrTenAriHZw3oDT6zDYjUzRNFyGZ1CVxj3BJ6pdb7fQw1Z7eMsD";
+        let expected_deposit_descriptor = format!("wpkh({}/0/*)", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Bitcoin, ScriptType::Wpkh).unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+    }
+
+    #[test]
+    fn test_create_descriptor_with_passphrase() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[647e2726/84'/1'/0']tpubDDKpcyAvAtgqKLU8B878mgMRgfTE3SWQzyVdNg8VsENSdVrXUefETW3xr96Q4th6923LBfj8EYNT5CFuhUiyLPe1b3v4HYQLmixRcixyLq8";
+        let expected_deposit_descriptor = format!("wpkh({}/0/*)", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "hunter2", Network::Testnet, ScriptType::Wpkh)
+                .unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+    }
+
+    #[test]
+    fn test_create_taproot_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[7b51f3f7/86'/1'/0']tpubDD5eHyn6r5rN58EmoHsDMdnxLSujmtEurfYypFq6F5jzQvheRUWsFjrKvHBJwsikpinpSUkkwvQCW7kAqmw7L39mWFxCVx8ohvX9GPccSga";
+        let expected_deposit_descriptor = format!("tr({}/0/*)", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::Tr).unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+        assert_eq!(descriptors.change, format!("tr({}/1/*)", expected_xpub));
+    }
+
+    #[test]
+    fn test_create_mainnet_taproot_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[7b51f3f7/86'/0'/0']xpub6BjQL6ydCnGjP3yVwzfjjJJ3SWbCpPnFCaqorFq9a79hWo3pqq5b1Vdj5FN9myV4TLSxLKUfVsqhub4xi6rYdtzqsBZ8EcdFkP1Guj3a9es";
+        let expected_deposit_descriptor = format!("tr({}/0/*)", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Bitcoin, ScriptType::Tr).unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+    }
+
+    #[test]
+    fn test_create_nested_segwit_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[7b51f3f7/49'/1'/0']tpubDCN1c7GXrhd6gBBe1n2MJYkmESFtbcf1SbxLePuLSt92mjhyD3v2xqtMrptTEfR5jjppnToJQhW1kFv3BB1avxh3AeWGCi2zE24DbcAk7aC";
+        let expected_deposit_descriptor = format!("sh(wpkh({}/0/*))", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::ShWpkh).unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+        assert_eq!(
+            descriptors.change,
+            format!("sh(wpkh({}/1/*))", expected_xpub)
+        );
+    }
+
+    #[test]
+    fn test_create_legacy_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected_xpub  = "[7b51f3f7/44'/1'/0']tpubDDjJUq7FsepdS4ErUkDjvFv498kHwCQ9ejA7Ue6z2jSGg8bLGCRgEPgb4Rb29b9NsTbKdAAftA2pSbA2hrBZBPDbNzX2y4SmktUJQYSjPTY";
+        let expected_deposit_descriptor = format!("pkh({}/0/*)", expected_xpub);
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::Pkh).unwrap();
+        assert_eq!(descriptors.deposit, expected_deposit_descriptor);
+    }
+
+    #[test]
+    fn test_create_multisig_descriptor() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let local = "[7b51f3f7/48'/1'/0'/2']tpubDEBkYQz2LWLxF47sFhf4rnTVd22SUpQyXEWzpYrwBeKMhXuYF2hc7FbwXKGQGt3sDwnTGBEmANA6S8ALNZmL7mARkKxrrr1tmeENrT3PLuX";
+        // the same mnemonic with bip39 passphrases hunter2 and hunter3
+        let cosigners = [
+            "[647e2726/48'/1'/0'/2']tpubDECMp3oG91pKGV4wAJAy7e6UbaKVPoBcBgpX4iYSFdHCdzgo7SR6oE1PAGsUYobbwS3351cUetp3DaKVUcfbuiFenJWc8DRYXaXF5KVL6US".to_string(),
+            "[85185bee/48'/1'/0'/2']tpubDERLapaLr8MfqnsTVYBuRzjcnay5RD9NwDnxS9GHdPGRj3ixSK6mXLUyrQLJ1kpjnhtQpXsCBPhdWvcuFUzbcsoK6NBgbJ5orbn56xGSZUH".to_string(),
+        ];
+        assert_eq!(
+            multisig_xpub(mnemonic, "", Network::Testnet).unwrap(),
+            local
+        );
+        let multisig = Multisig::new(2, &cosigners, Network::Testnet).unwrap();
+        let descriptors =
+            Descriptors::new_public_multisig(mnemonic, "", Network::Testnet, &multisig).unwrap();
+        assert_eq!(
+            descriptors.deposit,
+            format!(
+                "wsh(sortedmulti(2,{}/0/*,{}/0/*,{}/0/*))",
+                local, cosigners[0], cosigners[1]
+            )
+        );
+        assert!(descriptors
+            .change
+            .contains(&format!("{}/1/*", cosigners[1])));
+
+        assert!(Multisig::new(4, &cosigners, Network::Testnet).is_err());
+        assert!(Multisig::new(2, &cosigners, Network::Bitcoin).is_err());
+        let own_key = Multisig::new(2, &[local.to_string()], Network::Testnet).unwrap();
+        assert!(
+            Descriptors::new_public_multisig(mnemonic, "", Network::Testnet, &own_key).is_err()
+        );
+    }
+
+    #[test]
+    fn test_watch_only_descriptors() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let expected =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::Wpkh).unwrap();
+
+        let tpub = "[7b51f3f7/84'/1'/0']tpubDCCnk1bwtxqNaFbQstA7iGuzKkooWrZZ6HxHeEQ3dZbKCDftjW7pLGMjdwh1mKXK52SW6TYyoGjzFWaaSAVLCs7aq2Y4TZyaWgocm9GxuoQ";
+        let descriptors = Descriptors::from_xpub(tpub, Network::Testnet, None).unwrap();
+        assert_eq!(descriptors.deposit, expected.deposit);
+        assert_eq!(descriptors.change, expected.change);
+
+        // the same key as a vpub
+        let vpub = "[7b51f3f7/84'/1'/0']vpub5YAdcSSRtKnxbs2Kkybn6BPcehRFeE5ViSKYitZ8Xe5fLjNj9xwbwhNdMVh4F1GETqfyyvqzLrCqnkrqbup4ixJ62kPucDgNPH5vE16wKcV";
+        let descriptors = Descriptors::from_xpub(vpub, Network::Testnet, None).unwrap();
+        assert_eq!(descriptors.deposit, expected.deposit);
+
+        assert!(Descriptors::from_xpub(tpub, Network::Bitcoin, None).is_err());
+        assert!(Descriptors::from_xpub(vpub, Network::Testnet, Some(ScriptType::Tr)).is_err());
+
+        let descriptors = Descriptors::from_descriptor(&expected.deposit).unwrap();
+        assert_eq!(descriptors.change, expected.change);
+        assert_eq!(
+            ScriptType::of_descriptor(&descriptors.deposit),
+            Some(ScriptType::Wpkh)
+        );
+        let upub = "upub5DLNJmmWjeFUkZqCvcp9t6J7UjGohc5zoKoKwVfF9dhnHdZVuJn3KdiVLHjUF6cK4CZBETFRtBrHuUFGtDQ3vicVAQhV2Jrt7Z2GqP6FGaP";
+        let descriptors = Descriptors::from_xpub(upub, Network::Testnet, None).unwrap();
+        assert_eq!(
+            ScriptType::of_descriptor(&descriptors.deposit),
+            Some(ScriptType::ShWpkh)
+        );
+        let multisig = "wsh(sortedmulti(1,[7b51f3f7/48'/1'/0'/2']tpubDCCnk1bwtxqNaFbQstA7iGuzKkooWrZZ6HxHeEQ3dZbKCDftjW7pLGMjdwh1mKXK52SW6TYyoGjzFWaaSAVLCs7aq2Y4TZyaWgocm9GxuoQ/0/*))";
+        assert_eq!(ScriptType::of_descriptor(multisig), None);
+        let secret =
+            Descriptors::new_secret(mnemonic, "", Network::Testnet, ScriptType::Wpkh).unwrap();
+        assert!(Descriptors::from_descriptor(&secret.deposit).is_err());
+    }
+
+    #[test]
+    fn test_validate_mnemonic() {
+        let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
+        let spaced = format!("  {}\n", mnemonic.replace(' ', "   "));
+        assert_eq!(validate_mnemonic(&spaced).unwrap(), mnemonic);
+        // bad checksum
+        assert!(validate_mnemonic(&mnemonic.replace("absent", "abandon")).is_err());
+        // wrong word count
+        assert!(validate_mnemonic("rebel opinion faculty").is_err());
+    }
+
     #[test]
     fn test_wallet_ops() {
         let mnemonic = "rebel opinion faculty ticket wisdom shield ecology buyer wisdom dog fish below alcohol attack enact marriage ranch legal doll monkey sense click edit absent";
-        let descriptors = Descriptors::new_public(mnemonic).unwrap();
+        let descriptors =
+            Descriptors::new_public(mnemonic, "", Network::Testnet, ScriptType::Wpkh).unwrap();
         // let client = Client::new("ssl://electrum.blockstream.info:60002").unwrap();
         let sqlite_path: PathBuf = match std::env::var("HOME") {
             Ok(home_path) => {
@@ -129,7 +567,7 @@ mod test {
                 return;
             }
         };
-        let wallet = create_wallet(descriptors, &sqlite_path).unwrap();
+        let wallet = create_wallet(descriptors, Network::Testnet, &sqlite_path).unwrap();
         let first_address = wallet.get_address(Peek(0));
         println!("First Address: {:#?}", first_address);
     }
@@ -0,0 +1,323 @@
+//! Just enough of the boltz v1 http api for swappy: getpairs, createswap and swapstatus.
+//!
+//! Swap scripts are built the way boltz builds them. Reverse swaps are locked up from the
+//! bitcoind wallet on the first status request; submarine swap statuses are set by the test.
+
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::blockdata::opcodes::all::*;
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::hashes::{ripemd160, sha256, Hash};
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::script::{Builder, PushBytes};
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::secp256k1::{Secp256k1, SecretKey};
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::{Address, Amount, Network, PublicKey};
+use electrsd::bitcoind::bitcoincore_rpc::{Auth, Client, RpcApi};
+use electrsd::bitcoind::BitcoinD;
+use lightning_invoice::{Bolt11Invoice, Currency, InvoiceBuilder, PaymentSecret};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const PAIR_HASH: &str = "mock-pair-hash";
+const SUBMARINE_FEE: u64 = 1_000;
+const REVERSE_FEE: u64 = 1_000;
+const TIMEOUT_BLOCKS: u64 = 20;
+
+struct MockSwap {
+    status: String,
+    lockup_address: String,
+    onchain_amount: u64,
+    reverse: bool,
+}
+
+#[derive(Default)]
+struct State {
+    swaps: HashMap<String, MockSwap>,
+    next_id: u64,
+}
+
+pub struct MockBoltz {
+    port: u16,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockBoltz {
+    pub fn start(bitcoind: &BitcoinD) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(State::default()));
+        let rpc = Client::new(
+            &bitcoind.rpc_url_with_wallet("default"),
+            Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        )
+        .unwrap();
+
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => handle(stream, &server_state, &rpc),
+                    Err(_) => break,
+                }
+            }
+        });
+        MockBoltz { port, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// A regtest invoice boltz would be asked to pay. Its preimage is never revealed.
+    pub fn invoice(&self, sats: u64) -> String {
+        let preimage = sha256::Hash::hash(format!("preimage-{}", sats).as_bytes());
+        invoice(sha256::Hash::hash(preimage.as_byte_array()), sats).to_string()
+    }
+
+    pub fn set_status(&self, id: &str, status: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.swaps.get_mut(id).unwrap().status = status.to_string();
+    }
+
+    pub fn swap_ids(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.swaps.keys().cloned().collect()
+    }
+}
+
+fn node_key() -> SecretKey {
+    SecretKey::from_slice(&[0x11; 32]).unwrap()
+}
+
+fn boltz_pubkey() -> PublicKey {
+    PublicKey::new(node_key().public_key(&Secp256k1::new()))
+}
+
+fn invoice(payment_hash: sha256::Hash, sats: u64) -> Bolt11Invoice {
+    let secp = Secp256k1::new();
+    InvoiceBuilder::new(Currency::Regtest)
+        .description("swappy regtest".to_string())
+        .payment_hash(payment_hash)
+        .payment_secret(PaymentSecret([42; 32]))
+        .current_timestamp()
+        .min_final_cltv_expiry_delta(144)
+        .amount_milli_satoshis(sats * 1000)
+        .build_signed(|hash| secp.sign_ecdsa_recoverable(hash, &node_key()))
+        .unwrap()
+}
+
+fn hash160(payment_hash: &sha256::Hash) -> ripemd160::Hash {
+    ripemd160::Hash::hash(payment_hash.as_byte_array())
+}
+
+fn push_bytes(bytes: &[u8]) -> &PushBytes {
+    <&PushBytes>::try_from(bytes).unwrap()
+}
+
+fn submarine_script(
+    payment_hash: &sha256::Hash,
+    refund_pubkey: &PublicKey,
+    timeout: u64,
+) -> Builder {
+    Builder::new()
+        .push_opcode(OP_HASH160)
+        .push_slice(push_bytes(hash160(payment_hash).as_byte_array()))
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_key(&boltz_pubkey())
+        .push_opcode(OP_ELSE)
+        .push_int(timeout as i64)
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_key(refund_pubkey)
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_CHECKSIG)
+}
+
+fn reverse_script(payment_hash: &sha256::Hash, claim_pubkey: &PublicKey, timeout: u64) -> Builder {
+    Builder::new()
+        .push_opcode(OP_SIZE)
+        .push_int(32)
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_opcode(OP_HASH160)
+        .push_slice(push_bytes(hash160(payment_hash).as_byte_array()))
+        .push_opcode(OP_EQUALVERIFY)
+        .push_key(claim_pubkey)
+        .push_opcode(OP_ELSE)
+        .push_opcode(OP_DROP)
+        .push_int(timeout as i64)
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_key(&boltz_pubkey())
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_CHECKSIG)
+}
+
+fn handle(mut stream: TcpStream, state: &Arc<Mutex<State>>, rpc: &Client) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let response = if path.ends_with("/getpairs") {
+        get_pairs()
+    } else if path.ends_with("/createswap") {
+        create_swap(&request, state, rpc)
+    } else if path.ends_with("/swapstatus") {
+        swap_status(&request, state, rpc)
+    } else {
+        json!({ "error": format!("unknown path {}", path) })
+    };
+
+    let body = response.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+}
+
+fn get_pairs() -> Value {
+    json!({
+        "info": [],
+        "warnings": [],
+        "pairs": {
+            "BTC/BTC": {
+                "hash": PAIR_HASH,
+                "rate": 1,
+                "limits": {
+                    "maximal": 10_000_000,
+                    "minimal": 10_000,
+                    "maximalZeroConf": { "baseAsset": 0, "quoteAsset": 0 }
+                },
+                "fees": {
+                    "percentage": 0.5,
+                    "percentageSwapIn": 0.1,
+                    "minerFees": {
+                        "baseAsset": {
+                            "normal": SUBMARINE_FEE,
+                            "reverse": { "claim": 300, "lockup": REVERSE_FEE }
+                        },
+                        "quoteAsset": {
+                            "normal": SUBMARINE_FEE,
+                            "reverse": { "claim": 300, "lockup": REVERSE_FEE }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn create_swap(request: &Value, state: &Arc<Mutex<State>>, rpc: &Client) -> Value {
+    let timeout = rpc.get_block_count().unwrap() + TIMEOUT_BLOCKS;
+    let mut state = state.lock().unwrap();
+    state.next_id += 1;
+    let id = format!("mock{}", state.next_id);
+
+    match request["type"].as_str() {
+        Some("submarine") => {
+            let invoice = Bolt11Invoice::from_str(request["invoice"].as_str().unwrap()).unwrap();
+            let payment_hash =
+                sha256::Hash::from_byte_array(*invoice.payment_hash().as_byte_array());
+            let refund_pubkey =
+                PublicKey::from_str(request["refundPublicKey"].as_str().unwrap()).unwrap();
+            let script = submarine_script(&payment_hash, &refund_pubkey, timeout).into_script();
+            let address = Address::p2wsh(&script, Network::Regtest).to_string();
+            let expected_amount = invoice.amount_milli_satoshis().unwrap() / 1000 + SUBMARINE_FEE;
+            state.swaps.insert(
+                id.clone(),
+                MockSwap {
+                    status: "invoice.set".to_string(),
+                    lockup_address: address.clone(),
+                    onchain_amount: expected_amount,
+                    reverse: false,
+                },
+            );
+            json!({
+                "id": id,
+                "bip21": format!("bitcoin:{}?amount={}", address, Amount::from_sat(expected_amount).to_btc()),
+                "address": address,
+                "redeemScript": script.to_hex_string(),
+                "acceptZeroConf": false,
+                "expectedAmount": expected_amount,
+                "timeoutBlockHeight": timeout,
+            })
+        }
+        Some("reversesubmarine") => {
+            let payment_hash =
+                sha256::Hash::from_str(request["preimageHash"].as_str().unwrap()).unwrap();
+            let claim_pubkey =
+                PublicKey::from_str(request["claimPublicKey"].as_str().unwrap()).unwrap();
+            let invoice_amount = request["invoiceAmount"].as_u64().unwrap();
+            let script = reverse_script(&payment_hash, &claim_pubkey, timeout).into_script();
+            let address = Address::p2wsh(&script, Network::Regtest).to_string();
+            let onchain_amount = invoice_amount - REVERSE_FEE;
+            state.swaps.insert(
+                id.clone(),
+                MockSwap {
+                    status: "swap.created".to_string(),
+                    lockup_address: address.clone(),
+                    onchain_amount,
+                    reverse: true,
+                },
+            );
+            json!({
+                "id": id,
+                "invoice": invoice(payment_hash, invoice_amount).to_string(),
+                "redeemScript": script.to_hex_string(),
+                "lockupAddress": address,
+                "onchainAmount": onchain_amount,
+                "timeoutBlockHeight": timeout,
+            })
+        }
+        other => json!({ "error": format!("unsupported swap type {:?}", other) }),
+    }
+}
+
+fn swap_status(request: &Value, state: &Arc<Mutex<State>>, rpc: &Client) -> Value {
+    let id = request["id"].as_str().unwrap_or("");
+    let mut state = state.lock().unwrap();
+    let swap = match state.swaps.get_mut(id) {
+        Some(swap) => swap,
+        None => return json!({ "error": format!("could not find swap with id: {}", id) }),
+    };
+    // pretend the invoice got paid the moment swappy starts polling
+    if swap.reverse && swap.status == "swap.created" {
+        let address = Address::from_str(&swap.lockup_address)
+            .unwrap()
+            .assume_checked();
+        rpc.send_to_address(
+            &address,
+            Amount::from_sat(swap.onchain_amount),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        swap.status = "transaction.mempool".to_string();
+    }
+    json!({ "status": swap.status })
+}
@@ -0,0 +1,172 @@
+pub mod boltz;
+
+use electrsd::bitcoind::bitcoincore_rpc::bitcoin::{Address, Amount};
+use electrsd::bitcoind::bitcoincore_rpc::RpcApi;
+use electrsd::bitcoind::BitcoinD;
+use electrsd::electrum_client::ElectrumApi;
+use electrsd::ElectrsD;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// A regtest bitcoind + electrs pair, a mock boltz and a HOME to run swappy in.
+///
+/// Binaries are taken from BITCOIND_EXE and ELECTRS_EXE.
+pub struct TestEnv {
+    pub bitcoind: BitcoinD,
+    pub electrsd: ElectrsD,
+    pub boltz: boltz::MockBoltz,
+    pub home: PathBuf,
+}
+
+impl TestEnv {
+    pub fn new(name: &str) -> Self {
+        let bitcoind_exe = electrsd::bitcoind::exe_path().expect("set BITCOIND_EXE");
+        let bitcoind = BitcoinD::new(bitcoind_exe).unwrap();
+        let electrs_exe = electrsd::exe_path().expect("set ELECTRS_EXE");
+        let electrsd = ElectrsD::new(electrs_exe, &bitcoind).unwrap();
+
+        let miner = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &miner).unwrap();
+
+        let boltz = boltz::MockBoltz::start(&bitcoind);
+
+        let mut home = std::env::temp_dir();
+        home.push(format!("swappy-regtest-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(&home).unwrap();
+
+        let env = TestEnv {
+            bitcoind,
+            electrsd,
+            boltz,
+            home,
+        };
+        env.wait_for_tip();
+        env
+    }
+
+    /// Runs swappy with `args`, feeding `stdin` to its prompts.
+    pub fn swappy(&self, args: &[&str], stdin: &str) -> Output {
+        self.swappy_with_env(args, stdin, &[])
+    }
+
+    /// Like `swappy`, with extra environment variables for secrets read without echo.
+    pub fn swappy_with_env(&self, args: &[&str], stdin: &str, envs: &[(&str, &str)]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_swappy"))
+            .env("HOME", &self.home)
+            .env("SWAPPY_PASSPHRASE", "regtest")
+            .envs(envs.iter().copied())
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        println!("swappy {:?}", args);
+        println!("{}", String::from_utf8_lossy(&output.stdout));
+        eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+        output
+    }
+
+    pub fn create_wallet(&self) {
+        let electrum = self.electrsd.electrum_url.clone();
+        let boltz = self.boltz.url();
+        let output = self.swappy(
+            &[
+                "create",
+                "--electrum",
+                &electrum,
+                "--boltz",
+                &boltz,
+                "--network",
+                "regtest",
+            ],
+            "yes\n",
+        );
+        assert!(output.status.success());
+    }
+
+    pub fn new_address(&self) -> String {
+        let output = self.swappy(&["receive", "chain"], "");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().last().unwrap().trim_matches('"').to_string()
+    }
+
+    pub fn fund(&self, address: &str, sats: u64) {
+        let address = Address::from_str(address).unwrap().assume_checked();
+        self.bitcoind
+            .client
+            .send_to_address(
+                &address,
+                Amount::from_sat(sats),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        self.mine(1);
+    }
+
+    pub fn mine(&self, blocks: u64) {
+        let miner = self
+            .bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        self.bitcoind
+            .client
+            .generate_to_address(blocks, &miner)
+            .unwrap();
+        self.wait_for_tip();
+    }
+
+    /// Blocks until electrs has indexed bitcoind's tip.
+    pub fn wait_for_tip(&self) {
+        let height = self.bitcoind.client.get_block_count().unwrap() as usize;
+        for _ in 0..100 {
+            self.electrsd.trigger().unwrap();
+            if self
+                .electrsd
+                .client
+                .block_headers_subscribe()
+                .unwrap()
+                .height
+                >= height
+            {
+                return;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        panic!("electrs did not reach height {}", height);
+    }
+
+    pub fn sync(&self) {
+        let output = self.swappy(&["sync"], "");
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Sync Complete."));
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
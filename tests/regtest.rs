@@ -0,0 +1,332 @@
+//! End to end tests against a local regtest bitcoind, electrs and a mock boltz.
+//!
+//! These need the bitcoind and electrs binaries and are skipped by default:
+//! BITCOIND_EXE=/path/to/bitcoind ELECTRS_EXE=/path/to/electrs cargo test -- --ignored
+mod common;
+
+use common::TestEnv;
+use electrsd::bitcoind::bitcoincore_rpc::RpcApi;
+
+fn swap_list(env: &TestEnv) -> String {
+    let output = env.swappy(&["swaps", "list"], "");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+#[ignore]
+fn test_receive_chain_and_sync() {
+    let env = TestEnv::new("receive-chain");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 100_000);
+    env.sync();
+
+    let output = env.swappy(&["status"], "");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Confirmed Balance: 100000"));
+
+    let output = env.swappy(&["status", "--json"], "");
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["type"], "status");
+    assert_eq!(status["confirmed"], 100_000);
+    assert_eq!(status["transactions"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+#[ignore]
+fn test_receive_ln_claims_reverse_swap() {
+    let env = TestEnv::new("receive-ln");
+    env.create_wallet();
+
+    let output = env.swappy(&["receive", "ln", "50000", "--yes"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Received payment from boltz"));
+    assert!(swap_list(&env).contains("State: Claimed"));
+
+    env.mine(1);
+    env.sync();
+    let output = env.swappy(&["status"], "");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Confirmed Balance: 0\n"));
+}
+
+#[test]
+#[ignore]
+fn test_send_ln_funds_submarine_swap() {
+    let env = TestEnv::new("send-ln");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 200_000);
+    env.sync();
+
+    let invoice = env.boltz.invoice(50_000);
+    let output = env.swappy(&["send", "ln", &invoice, "--yes"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Swap Funded"));
+    assert!(swap_list(&env).contains("State: Funded"));
+
+    let id = env.boltz.swap_ids().pop().unwrap();
+    env.boltz.set_status(&id, "transaction.claimed");
+    let output = env.swappy(&["swaps", "resume"], "");
+    assert!(output.status.success());
+    assert!(swap_list(&env).contains("State: Settled"));
+}
+
+#[test]
+#[ignore]
+fn test_failed_submarine_swap_is_refunded_after_timeout() {
+    let env = TestEnv::new("refund");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 200_000);
+    env.sync();
+
+    let invoice = env.boltz.invoice(50_000);
+    env.swappy(&["send", "ln", &invoice, "--yes"], "");
+    let id = env.boltz.swap_ids().pop().unwrap();
+    env.mine(1);
+    env.boltz.set_status(&id, "invoice.failedToPay");
+
+    // before the timeout the refund must wait
+    env.swappy(&["swaps", "resume"], "");
+    assert!(swap_list(&env).contains("State: Refundable"));
+
+    env.mine(25);
+    let output = env.swappy(&["swaps", "resume"], "");
+    assert!(output.status.success());
+    assert!(swap_list(&env).contains("State: Refunded"));
+}
+
+#[test]
+#[ignore]
+fn test_restore_rescans_history() {
+    let env = TestEnv::new("restore");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 100_000);
+
+    let output = env.swappy(&["backup", "show-seed"], "yes\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mnemonic = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Your mnemonic is: "))
+        .unwrap()
+        .to_string();
+    assert!(env.swappy(&["delete"], "yes\n").status.success());
+
+    let electrum = env.electrsd.electrum_url.clone();
+    let boltz = env.boltz.url();
+    let output = env.swappy_with_env(
+        &[
+            "restore",
+            "--electrum",
+            &electrum,
+            "--boltz",
+            &boltz,
+            "--network",
+            "regtest",
+        ],
+        "",
+        &[("SWAPPY_MNEMONIC", &mnemonic)],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Restore Complete. Confirmed Balance: 100000"));
+}
+
+#[test]
+#[ignore]
+fn test_named_wallets_are_separate() {
+    let env = TestEnv::new("wallets");
+    env.create_wallet();
+    let electrum = env.electrsd.electrum_url.clone();
+    let boltz = env.boltz.url();
+    let output = env.swappy(
+        &[
+            "wallets",
+            "create",
+            "treasury",
+            "--electrum",
+            &electrum,
+            "--boltz",
+            &boltz,
+            "--network",
+            "regtest",
+        ],
+        "yes\n",
+    );
+    assert!(output.status.success());
+
+    let output = env.swappy(&["wallets", "list"], "");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("* default"));
+    assert!(stdout.contains("  treasury"));
+
+    let address = env.new_address();
+    let output = env.swappy(&["receive", "chain", "--wallet", "treasury"], "");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains(&address));
+
+    assert!(env
+        .swappy(&["wallets", "switch", "treasury"], "")
+        .status
+        .success());
+    assert_ne!(env.new_address(), address);
+    assert!(env
+        .swappy(&["wallets", "delete", "treasury"], "yes\n")
+        .status
+        .success());
+    assert_eq!(env.new_address(), address);
+}
+
+#[test]
+#[ignore]
+fn test_bump_replaces_unconfirmed_send() {
+    let env = TestEnv::new("bump");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 200_000);
+    env.sync();
+
+    let address = env.new_address();
+    let output = env.swappy(
+        &[
+            "send",
+            "chain",
+            &address,
+            "0.0005",
+            "--fee-rate",
+            "2",
+            "--yes",
+            "--json",
+        ],
+        "",
+    );
+    let sent: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let txid = sent["txid"].as_str().unwrap();
+
+    let output = env.swappy(&["bump", txid, "--fee-rate", "10", "--yes", "--json"], "");
+    let bumped: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_ne!(bumped["txid"], sent["txid"]);
+    assert!(bumped["fee"].as_u64() > sent["fee"].as_u64());
+
+    env.mine(1);
+    let output = env.swappy(&["bump", txid, "--fee-rate", "20", "--yes", "--json"], "");
+    assert!(!output.status.success());
+    let failed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(failed["error"]
+        .as_str()
+        .unwrap()
+        .contains("is already confirmed"));
+}
+
+#[test]
+#[ignore]
+fn test_cpfp_accelerates_claim() {
+    let env = TestEnv::new("cpfp");
+    env.create_wallet();
+    env.swappy(&["receive", "ln", "50000", "--yes"], "");
+    env.sync();
+
+    let output = env.swappy(&["status", "--json"], "");
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let claim = status["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tx| tx["confirmation_height"].is_null())
+        .unwrap();
+    let txid = claim["txid"].as_str().unwrap();
+
+    let output = env.swappy(&["cpfp", txid, "--fee-rate", "20", "--yes", "--json"], "");
+    let child: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_ne!(child["txid"].as_str().unwrap(), txid);
+    assert!(child["fee"].as_u64().unwrap() > 300);
+}
+
+#[test]
+#[ignore]
+fn test_frozen_coins_are_not_spent() {
+    let env = TestEnv::new("utxos");
+    env.create_wallet();
+    let address = env.new_address();
+    env.fund(&address, 100_000);
+    env.fund(&env.new_address(), 100_000);
+    env.sync();
+
+    let output = env.swappy(&["utxos", "list", "--json"], "");
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let utxos = list["utxos"].as_array().unwrap();
+    assert_eq!(utxos.len(), 2);
+    let outpoint = utxos[0]["outpoint"].as_str().unwrap();
+    assert!(env
+        .swappy(&["utxos", "freeze", outpoint], "")
+        .status
+        .success());
+
+    // only the other coin may be spent
+    let output = env.swappy(
+        &["send", "chain", &address, "0.0015", "--yes", "--json"],
+        "",
+    );
+    assert!(!output.status.success());
+    let failed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(failed["error"]
+        .as_str()
+        .unwrap()
+        .contains("Insufficient funds"));
+    let output = env.swappy(
+        &[
+            "send", "chain", &address, "0.0005", "--utxo", outpoint, "--yes", "--json",
+        ],
+        "",
+    );
+    assert!(!output.status.success());
+    let failed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(failed["error"].as_str().unwrap().contains("is frozen"));
+    let output = env.swappy(
+        &["send", "chain", &address, "0.0005", "--yes", "--json"],
+        "",
+    );
+    assert!(output.status.success());
+
+    env.sync();
+    let output = env.swappy(&["utxos", "list", "--json"], "");
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(list["utxos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|utxo| utxo["outpoint"] == outpoint && utxo["frozen"] == true));
+}
+
+#[test]
+#[ignore]
+fn test_send_max_empties_wallet() {
+    let env = TestEnv::new("send-max");
+    env.create_wallet();
+    env.fund(&env.new_address(), 100_000);
+    env.fund(&env.new_address(), 50_000);
+    env.sync();
+
+    let address = env.new_address();
+    let output = env.swappy(&["send", "chain", &address, "0.001", "--max", "--yes"], "");
+    assert!(!output.status.success());
+
+    let external = env
+        .bitcoind
+        .client
+        .get_new_address(None, None)
+        .unwrap()
+        .assume_checked()
+        .to_string();
+    let sent = env.swappy(
+        &["send", "chain", &external, "--max", "--yes", "--json"],
+        "",
+    );
+    assert!(sent.status.success());
+    env.mine(1);
+    env.sync();
+
+    let output = env.swappy(&["status", "--json"], "");
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["confirmed"], 0);
+    assert_eq!(status["unconfirmed"], 0);
+}